@@ -0,0 +1,91 @@
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use super::syntax::KEYWORDS;
+
+// Most cases a single test command will run, since each one is a full execution
+pub const MAX_CASES: usize = 25;
+
+lazy_static! {
+    // `// expect :out==5 after 100`, where the `after` part is optional
+    static ref COMMENT_MATCHER: Regex = Regex::new(r"(?i)//\s*expect\s+(.+?)(?:\s+after\s+(\d+))?\s*$").expect("Expect comment regex failed to compile!");
+    // Same thing for lines of a test block, where the `expect` is optional too
+    static ref TEST_LINE_MATCHER: Regex = Regex::new(r"(?i)^\s*(?:expect\s+)?(.+?)(?:\s+after\s+(\d+))?\s*$").expect("Test line regex failed to compile!");
+}
+
+/// A condition that should hold once the program has run for some number of ticks.
+/// `ticks` is `None` when the case didn't say, meaning the configured tick limit is used.
+#[derive(Debug, Clone)]
+pub struct Assertion
+{
+    pub condition: String,
+    pub ticks: Option<usize>,
+    // Where the assertion was written, 1-indexed
+    pub line: usize,
+}
+
+fn from_captures(captures: regex::Captures, line: usize) -> Result<Assertion, String>
+{
+    let condition = captures[1].trim().to_owned();
+
+    let ticks = match captures.get(2)
+    {
+        Some(ticks) => Some(ticks.as_str().parse::<usize>()
+            .map_err(|error| format!("Couldn't read the tick count of the assertion on line {}! Error: ```{}```", line, error))?),
+        None => None,
+    };
+
+    Ok(Assertion {
+        condition,
+        ticks,
+        line,
+    })
+}
+
+/// Finds `// expect <condition> [after <ticks>]` comments in the code
+pub fn from_comments(code: &str) -> Result<Vec<Assertion>, String>
+{
+    code.lines()
+        .enumerate()
+        .filter_map(|(index, line)| COMMENT_MATCHER.captures(line).map(|captures| from_captures(captures, index + 1)))
+        .collect()
+}
+
+/// Reads a companion test block, one `[expect] <condition> [after <ticks>]` per line.
+/// Blank lines and lines starting with `//` are skipped.
+pub fn from_test_block(block: &str) -> Result<Vec<Assertion>, String>
+{
+    block.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with("//"))
+        .map(|(index, line)| match TEST_LINE_MATCHER.captures(line)
+        {
+            Some(captures) => from_captures(captures, index + 1),
+            None => Err(format!("Couldn't make sense of line {} of the test block: `{}`", index + 1, line.trim())),
+        })
+        .collect()
+}
+
+/// Pulls the names of any variables out of a condition, so failures can show what they ended up as.
+/// Keywords are left out, since they'd never be in the environment anyway.
+pub fn mentioned_variables(condition: &str) -> Vec<String>
+{
+    lazy_static! {
+        static ref IDENTIFIER_MATCHER: Regex = Regex::new(r#""[^"]*"|(:?[A-Za-z_][A-Za-z0-9_.]*)"#).expect("Identifier regex failed to compile!");
+    }
+
+    let mut names: Vec<String> = vec![];
+
+    for name in IDENTIFIER_MATCHER.captures_iter(condition).filter_map(|captures| captures.get(1))
+    {
+        let name = name.as_str();
+        let seen = names.iter().any(|seen| seen.eq_ignore_ascii_case(name));
+
+        if !seen && !KEYWORDS.contains(&name.to_lowercase().as_str())
+        {
+            names.push(name.to_owned());
+        }
+    }
+
+    names
+}
@@ -4,14 +4,47 @@ use serenity::framework::standard::{
 
 use cylon_ast::CylonRoot;
 
-#[derive(Debug)]
+use super::control_flow::GraphFormat;
+
+use regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // Matches `name=value` where the name is a local or a `:field` and the value is a number or a quoted string
+    static ref INITIAL_VALUE_MATCHER: Regex = Regex::new(r#"\A(:?[a-zA-Z_][a-zA-Z0-9_]*)=(-?[0-9]+(?:\.[0-9]+)?|"[^"]*")\z"#).expect("Initial value regex failed to compile!");
+    // Matches an inclusive whole number range like `0..5` or `-2..2`
+    static ref SWEEP_RANGE_MATCHER: Regex = Regex::new(r"\A(-?[0-9]+)\.\.(-?[0-9]+)\z").expect("Sweep range regex failed to compile!");
+}
+
+#[derive(Debug, Clone)]
 pub struct YololConfig
 {
     pub input: InputFlag,
     pub output: OutputFlag,
+
+    // Execution budget and the optional early stop conditions
+    pub tick_limit: usize,
+    pub stop_condition: Option<String>,
+    pub stop_on_settle: bool,
+
+    // Variables to seed the environment with before running, as (name, yolol literal) pairs
+    pub initial_values: Vec<(String, String)>,
+
+    // Variables to run over every value of, with one run per combination, and the fields to show for each run
+    pub sweeps: Vec<(String, Vec<String>)>,
+    pub sweep_fields: Vec<String>,
+
+    // The line the debugger's run to line button stops at
+    pub breakpoint: Option<usize>,
+
+    // A stored environment to start from, which gets written back once the run finishes
+    pub environment: Option<String>,
+
+    // Whether the last input is a json environment to start from, rather than code
+    pub environment_input: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum InputFlag
 {
     Yolol,
@@ -25,23 +58,48 @@ pub enum YololInput
     CylonAst(CylonRoot)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum OutputFlag
 {
     Execution,
     Yolol,
     CylonAst,
     Ast,
-    Tokens
+    Tokens,
+    Trace,
+    Lint,
+    Minified,
+    ControlFlow(GraphFormat),
+    Variables,
+    Profile,
+    Optimized,
+    EnvironmentJson
 }
 
 impl YololConfig
 {
+    pub const DEFAULT_TICK_LIMIT: usize = 1000;
+    pub const MAX_TICK_LIMIT: usize = 100_000;
+
     pub fn new() -> Self
     {
         YololConfig {
             input: InputFlag::Yolol,
             output: OutputFlag::Execution,
+
+            tick_limit: Self::DEFAULT_TICK_LIMIT,
+            stop_condition: None,
+            stop_on_settle: false,
+
+            initial_values: vec![],
+
+            sweeps: vec![],
+            sweep_fields: vec![],
+
+            breakpoint: None,
+
+            environment: None,
+            environment_input: false,
         }
     }
 
@@ -49,14 +107,9 @@ impl YololConfig
     {
         let mut config = YololConfig::new();
 
-        loop
+        // Code can come from an attachment, so running out of args just means we're done with flags
+        while let Some(current) = args.current()
         {
-            let current = match args.current()
-            {
-                Some(args) => args,
-                None => return Err("Unable to get an argument! Might not have supplied any args...".to_owned())
-            };
-
             println!("Current arg: {}", current);
 
             match current
@@ -64,6 +117,9 @@ impl YololConfig
                 "--input=cylon_ast" |
                 "-ic" => config.input = InputFlag::CylonAst,
 
+                "--input=env_json" |
+                "-ie" => config.environment_input = true,
+
                 "--output=yolol" |
                 "--output=code" |
                 "-oy" => config.output = OutputFlag::Yolol,
@@ -78,6 +134,96 @@ impl YololConfig
                 "--output=tokens" |
                 "-ot" => config.output = OutputFlag::Tokens,
 
+                "--output=trace" |
+                "-otr" => config.output = OutputFlag::Trace,
+
+                "--output=lint" |
+                "-ol" => config.output = OutputFlag::Lint,
+
+                "--output=minified" |
+                "-om" => config.output = OutputFlag::Minified,
+
+                "--output=cfg" |
+                "-og" => config.output = OutputFlag::ControlFlow(GraphFormat::Dot),
+
+                "--output=cfg_mermaid" |
+                "-ogm" => config.output = OutputFlag::ControlFlow(GraphFormat::Mermaid),
+
+                "--output=vars" |
+                "-ov" => config.output = OutputFlag::Variables,
+
+                "--output=profile" |
+                "-op" => config.output = OutputFlag::Profile,
+
+                "--output=optimized" |
+                "-oo" => config.output = OutputFlag::Optimized,
+
+                "--output=env_json" |
+                "-oe" => config.output = OutputFlag::EnvironmentJson,
+
+                "--settle" |
+                "-s" => config.stop_on_settle = true,
+
+                "--set" => {
+                    let assignment = match args.advance().current()
+                    {
+                        Some(assignment) => assignment,
+                        None => return Err("The `--set` flag needs a value after it, like `--set :button=1`".to_owned())
+                    };
+
+                    config.initial_values.push(Self::parse_initial_value(assignment)?);
+                },
+
+                "--sweep" => {
+                    let sweep = match args.advance().current()
+                    {
+                        Some(sweep) => sweep,
+                        None => return Err("The `--sweep` flag needs a value after it, like `--sweep :a=0..5` or `--sweep :b=1,2`".to_owned())
+                    };
+
+                    config.sweeps.push(Self::parse_sweep(sweep)?);
+                },
+
+                flag if flag.starts_with("--fields=") => {
+                    config.sweep_fields = flag["--fields=".len()..].split(',')
+                        .map(|field| field.trim().to_owned())
+                        .filter(|field| !field.is_empty())
+                        .collect();
+                },
+
+                flag if flag.starts_with("--env=") => {
+                    let name = &flag["--env=".len()..];
+                    super::snippets::validate_name(name)?;
+                    config.environment = Some(name.to_owned());
+                },
+
+                flag if flag.starts_with("--break=") => {
+                    let line = &flag["--break=".len()..];
+                    config.breakpoint = match line.parse::<usize>()
+                    {
+                        Ok(line) if line > 0 => Some(line),
+                        _ => return Err(format!("Breakpoints have to be a line number! Got: `{}`", line))
+                    };
+                },
+
+                flag if flag.starts_with("--ticks=") => {
+                    let ticks = &flag["--ticks=".len()..];
+                    config.tick_limit = match ticks.parse::<usize>()
+                    {
+                        Ok(ticks) if ticks > 0 && ticks <= Self::MAX_TICK_LIMIT => ticks,
+                        _ => return Err(format!("Tick count must be a number from 1 to {}! Got: `{}`", Self::MAX_TICK_LIMIT, ticks))
+                    };
+                },
+
+                flag if flag.starts_with("--until=") => {
+                    let condition = &flag["--until=".len()..];
+                    if condition.is_empty()
+                    {
+                        return Err("The `--until=` flag needs a condition, like `--until=:done==1`".to_owned())
+                    }
+                    config.stop_condition = Some(condition.to_owned());
+                },
+
                 _ => break
             }
 
@@ -86,5 +232,84 @@ impl YololConfig
 
         Ok(config)
     }
+
+    pub fn parse_initial_value(assignment: &str) -> Result<(String, String), String>
+    {
+        let captures = match INITIAL_VALUE_MATCHER.captures(assignment)
+        {
+            Some(captures) => captures,
+            None => return Err(format!("Couldn't understand `--set {}`! Values look like `:field=1`, `local=-2.5` or `:name=\"abc\"` (no spaces)", assignment))
+        };
+
+        Ok((captures[1].to_owned(), captures[2].to_owned()))
+    }
+
+    // Sweeps are either an inclusive range of whole numbers, `:a=0..5`, or a list of values, `:b=1,2,"on"`
+    fn parse_sweep(sweep: &str) -> Result<(String, Vec<String>), String>
+    {
+        let (name, values) = match sweep.find('=')
+        {
+            Some(index) => (&sweep[..index], &sweep[index + 1..]),
+            None => return Err(format!("Couldn't understand `--sweep {}`! Sweeps look like `:a=0..5` or `:b=1,2` (no spaces)", sweep))
+        };
+
+        let values: Vec<String> = match SWEEP_RANGE_MATCHER.captures(values)
+        {
+            Some(captures) => {
+                let (start, end) = match (captures[1].parse::<i64>(), captures[2].parse::<i64>())
+                {
+                    (Ok(start), Ok(end)) if start <= end => (start, end),
+                    _ => return Err(format!("The range in `--sweep {}` has to go from a smaller number to a bigger one!", sweep))
+                };
+
+                // Anything this big would blow the combination cap anyway. The span itself can overflow for huge ranges
+                let too_big = match end.checked_sub(start)
+                {
+                    Some(span) => span >= super::sweep::MAX_COMBINATIONS as i64,
+                    None => true,
+                };
+                if too_big
+                {
+                    return Err(format!("The range in `--sweep {}` has too many values! A sweep can do at most {} runs", sweep, super::sweep::MAX_COMBINATIONS))
+                }
+
+                (start..=end).map(|value| value.to_string()).collect()
+            },
+            None => values.split(',').map(String::from).collect(),
+        };
+
+        // The name and every value have to be something `--set` would take
+        if Self::parse_initial_value(&format!("{}=0", name)).is_err()
+        {
+            return Err(format!("`{}` in `--sweep {}` isn't a variable name! Names look like `local` or `:field`", name, sweep))
+        }
+
+        let values = values.iter()
+            .map(|value| match Self::parse_initial_value(&format!("{}={}", name, value))
+            {
+                Ok((_, value)) => Ok(value),
+                Err(_) => Err(format!("Couldn't understand `{}` in `--sweep {}`! Values are numbers or quoted strings, like `1`, `-2.5` or `\"abc\"`", value, sweep))
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+
+        Ok((name.to_owned(), values))
+    }
 }
 
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn sweep_ranges_include_both_ends()
+    {
+        assert_eq!(YololConfig::parse_sweep(":a=-1..1"), Ok((":a".to_owned(), vec!["-1".to_owned(), "0".to_owned(), "1".to_owned()])));
+    }
+
+    #[test]
+    fn huge_sweep_ranges_are_refused_without_overflowing()
+    {
+        assert!(YololConfig::parse_sweep("a=-5000000000000000000..5000000000000000000").is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::{
+    BTreeSet,
+    VecDeque,
+};
+
+use cylon_ast::{
+    CylonProg,
+    CylonStat,
+    CylonExpr,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum GraphFormat
+{
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind
+{
+    // Falling off the end of a line into the next one
+    Fallthrough,
+    // A goto that always runs
+    Goto,
+    // A goto inside an if, which may or may not run
+    ConditionalGoto,
+}
+
+#[derive(Debug)]
+pub struct Edge
+{
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// Line to line flow of a program. Lines are 1-indexed.
+///
+/// Runtime errors also skip to the next line, but they aren't modeled here since
+/// nearly any line can error. Execution wraps back to line 1 after the last line.
+/// Goto targets are rounded down and clamped to lines 1 to 20 like yoloxide does it,
+/// and a target past the end of a shorter program lands on line 1, same as the executor.
+#[derive(Debug)]
+pub struct ControlFlow
+{
+    pub line_count: usize,
+    pub edges: Vec<Edge>,
+    // Lines with a goto whose target depends on runtime values
+    pub computed_gotos: BTreeSet<usize>,
+}
+
+impl ControlFlow
+{
+    pub fn analyze(prog: &CylonProg) -> Self
+    {
+        let line_count = prog.lines.len();
+        let mut edges = vec![];
+        let mut computed_gotos = BTreeSet::new();
+
+        for (index, line) in prog.lines.iter().enumerate()
+        {
+            let from = index + 1;
+
+            let mut gotos = vec![];
+            collect_gotos(&line.code, false, &mut gotos);
+
+            for (target, conditional) in gotos
+            {
+                let kind = if conditional { EdgeKind::ConditionalGoto } else { EdgeKind::Goto };
+
+                match constant_target(target)
+                {
+                    Some(to) => edges.push(Edge { from, to: wrap_target(to, line_count), kind }),
+                    None => { computed_gotos.insert(from); },
+                }
+            }
+
+            if !always_jumps(&line.code)
+            {
+                let to = if from >= line_count { 1 } else { from + 1 };
+                edges.push(Edge { from, to, kind: EdgeKind::Fallthrough });
+            }
+        }
+
+        ControlFlow {
+            line_count,
+            edges,
+            computed_gotos,
+        }
+    }
+
+    /// Lines that can't be reached from line 1 by following known edges
+    pub fn unreachable_lines(&self) -> Vec<usize>
+    {
+        let mut reached = BTreeSet::new();
+        let mut queue = VecDeque::new();
+
+        if self.line_count > 0
+        {
+            reached.insert(1);
+            queue.push_back(1);
+        }
+
+        while let Some(line) = queue.pop_front()
+        {
+            for edge in self.edges.iter().filter(|edge| edge.from == line)
+            {
+                if reached.insert(edge.to)
+                {
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+
+        (1..=self.line_count)
+            .filter(|line| !reached.contains(line))
+            .collect()
+    }
+
+    /// Renders the graph, labeling each node with its line of code
+    pub fn render(&self, format: GraphFormat, code_lines: &[String]) -> String
+    {
+        let unreachable = self.unreachable_lines();
+        let label = |line: usize| {
+            let code = code_lines.get(line - 1).map(|code| code.trim()).unwrap_or("");
+            format!("{}: {}", line, code)
+        };
+
+        let mut output = vec![];
+
+        match format
+        {
+            GraphFormat::Dot => {
+                output.push("digraph yolol {".to_owned());
+                output.push("    node [shape=box, fontname=monospace];".to_owned());
+
+                for line in 1..=self.line_count
+                {
+                    let style = if unreachable.contains(&line) { ", style=filled, fillcolor=lightgrey" } else { "" };
+                    output.push(format!("    L{} [label=\"{}\"{}];", line, dot_escape(&label(line)), style));
+                }
+
+                for edge in &self.edges
+                {
+                    let attributes = match edge.kind
+                    {
+                        EdgeKind::Fallthrough => "",
+                        EdgeKind::Goto => " [label=\"goto\"]",
+                        EdgeKind::ConditionalGoto => " [label=\"if goto\", style=dashed]",
+                    };
+                    output.push(format!("    L{} -> L{}{};", edge.from, edge.to, attributes));
+                }
+
+                if !self.computed_gotos.is_empty()
+                {
+                    output.push("    unknown [label=\"computed goto\", shape=ellipse];".to_owned());
+                    for line in &self.computed_gotos
+                    {
+                        output.push(format!("    L{} -> unknown [label=\"goto ?\", style=dotted];", line));
+                    }
+                }
+
+                output.push("}".to_owned());
+            },
+
+            GraphFormat::Mermaid => {
+                output.push("flowchart TD".to_owned());
+
+                for line in 1..=self.line_count
+                {
+                    output.push(format!("    L{}[\"{}\"]", line, mermaid_escape(&label(line))));
+                }
+
+                for edge in &self.edges
+                {
+                    let arrow = match edge.kind
+                    {
+                        EdgeKind::Fallthrough => "-->",
+                        EdgeKind::Goto => "-->|goto|",
+                        EdgeKind::ConditionalGoto => "-.->|if goto|",
+                    };
+                    output.push(format!("    L{} {} L{}", edge.from, arrow, edge.to));
+                }
+
+                if !self.computed_gotos.is_empty()
+                {
+                    output.push("    unknown((\"computed goto\"))".to_owned());
+                    for line in &self.computed_gotos
+                    {
+                        output.push(format!("    L{} -.->|goto ?| unknown", line));
+                    }
+                }
+
+                if !unreachable.is_empty()
+                {
+                    output.push("    classDef unreachable fill:#ddd,color:#777".to_owned());
+                    let nodes: Vec<String> = unreachable.iter().map(|line| format!("L{}", line)).collect();
+                    output.push(format!("    class {} unreachable", nodes.join(",")));
+                }
+            },
+        }
+
+        output.join("\n")
+    }
+}
+
+// Follows yoloxide's evaluate_goto, then the executor's wrap for lines past the end
+fn wrap_target(target: f64, line_count: usize) -> usize
+{
+    let target = target.floor().clamp(1.0, 20.0) as usize;
+    if target <= line_count { target } else { 1 }
+}
+
+// Anything after a statement that always jumps never runs, so its gotos can't be taken
+
+fn collect_gotos<'a>(stats: &'a [CylonStat], conditional: bool, gotos: &mut Vec<(&'a CylonExpr, bool)>)
+{
+    for stat in stats
+    {
+        match stat
+        {
+            CylonStat::Goto { expression } => gotos.push((expression, conditional)),
+            CylonStat::If { body, else_body, .. } => {
+                collect_gotos(body, true, gotos);
+                collect_gotos(else_body, true, gotos);
+            },
+            _ => (),
+        }
+
+        if always_jumps(std::slice::from_ref(stat))
+        {
+            break;
+        }
+    }
+}
+
+/// Whether running these statements always ends in a goto, meaning the line never falls through
+pub fn always_jumps(stats: &[CylonStat]) -> bool
+{
+    stats.iter().any(|stat| match stat
+    {
+        CylonStat::Goto { .. } => true,
+        CylonStat::If { body, else_body, .. } => always_jumps(body) && always_jumps(else_body),
+        _ => false,
+    })
+}
+
+// Only number literals count as a static target, anything else needs runtime values
+fn constant_target(expr: &CylonExpr) -> Option<f64>
+{
+    match expr
+    {
+        CylonExpr::Group { group } => constant_target(group),
+        CylonExpr::UnaryOp { operator, operand } if operator == "-" => constant_target(operand).map(|target| -target),
+        CylonExpr::Number { num } => num.parse().ok(),
+        _ => None,
+    }
+}
+
+fn dot_escape(text: &str) -> String
+{
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String
+{
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::parse_yolol;
+    use super::super::config::YololInput;
+
+    fn analyzed(code: &str) -> ControlFlow
+    {
+        let prog = parse_yolol(YololInput::Yolol(code.to_owned())).expect("Test code failed to parse!");
+        ControlFlow::analyze(&prog.into())
+    }
+
+    fn goto_targets(flow: &ControlFlow, from: usize) -> Vec<usize>
+    {
+        flow.edges.iter()
+            .filter(|edge| edge.from == from && edge.kind != EdgeKind::Fallthrough)
+            .map(|edge| edge.to)
+            .collect()
+    }
+
+    #[test]
+    fn targets_are_clamped_like_yoloxide()
+    {
+        let mut lines = vec!["goto 25".to_owned(), "goto 0".to_owned(), "goto -3".to_owned()];
+        lines.resize(20, "a = 1".to_owned());
+        let flow = analyzed(&lines.join("\n"));
+
+        assert_eq!(goto_targets(&flow, 1), vec![20]);
+        assert_eq!(goto_targets(&flow, 2), vec![1]);
+        assert_eq!(goto_targets(&flow, 3), vec![1]);
+    }
+
+    #[test]
+    fn targets_past_a_short_program_wrap_to_the_start()
+    {
+        let flow = analyzed("a = 1\ngoto 25\nb = 2");
+        assert_eq!(goto_targets(&flow, 2), vec![1]);
+    }
+
+    #[test]
+    fn fractional_targets_are_rounded_down()
+    {
+        let flow = analyzed("goto 2.5\na = 1\nb = 2");
+        assert_eq!(goto_targets(&flow, 1), vec![2]);
+        assert!(flow.computed_gotos.is_empty());
+    }
+
+    #[test]
+    fn computed_targets_are_reported()
+    {
+        let flow = analyzed("goto a\nb = 2");
+        assert!(goto_targets(&flow, 1).is_empty());
+        assert!(flow.computed_gotos.contains(&1));
+    }
+
+    #[test]
+    fn gotos_after_an_unconditional_goto_never_run()
+    {
+        let flow = analyzed("goto 4 goto 2\na = 1\nb = 2\nif a then goto 1 goto 3 else goto 1 end goto 2");
+        assert_eq!(goto_targets(&flow, 1), vec![4]);
+        assert_eq!(goto_targets(&flow, 4), vec![1, 1]);
+        assert_eq!(flow.unreachable_lines(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use yoloxide::environment::Environment;
+
+use lazy_static::lazy_static;
+
+use super::config::YololConfig;
+use super::execution::{
+    self,
+    TIME_LIMIT,
+    MAX_TOTAL_STRING_LENGTH,
+};
+
+// Sessions end on their own so they can't pile up
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+pub const MAX_SESSION_LENGTH: Duration = Duration::from_secs(15 * 60);
+pub const MAX_SESSIONS: usize = 5;
+
+// Longest the rendered session can get and still fit in a message
+const MAX_RENDER_LENGTH: usize = 1900;
+
+lazy_static! {
+    // Users with a session running. Everyone gets one at a time
+    static ref OWNERS: Mutex<HashSet<u64>> = Mutex::new(HashSet::new());
+}
+
+/// Reserves a session for a user. Fails if they already have one or there's too many running.
+pub fn claim(owner: u64) -> Result<(), String>
+{
+    let mut owners = OWNERS.lock().expect("Debug session owners were poisoned!");
+
+    if owners.contains(&owner)
+    {
+        return Err("You already have a debug session running! Press ⏹ on it to stop it first".to_owned())
+    }
+
+    if owners.len() >= MAX_SESSIONS
+    {
+        return Err(format!("There's already {} debug sessions running, which is the most I can handle! Try again in a bit", MAX_SESSIONS))
+    }
+
+    owners.insert(owner);
+    Ok(())
+}
+
+pub fn release(owner: u64)
+{
+    OWNERS.lock().expect("Debug session owners were poisoned!").remove(&owner);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action
+{
+    Step(usize),
+    RunToLine,
+    Continue,
+    Stop,
+}
+
+// The reaction buttons, in the order they're added to the message
+pub const BUTTONS: &[(&str, Action)] = &[
+    ("▶", Action::Step(1)),
+    ("⏩", Action::Step(10)),
+    ("⏭", Action::RunToLine),
+    ("⏯", Action::Continue),
+    ("⏹", Action::Stop),
+];
+
+impl Action
+{
+    pub fn from_emoji(emoji: &str) -> Option<Action>
+    {
+        // Discord sometimes tacks a variation selector onto the emoji we sent
+        let emoji = emoji.trim_end_matches('\u{FE0F}');
+
+        BUTTONS.iter()
+            .find(|(button, _)| *button == emoji)
+            .map(|(_, action)| *action)
+    }
+}
+
+enum RunEnd
+{
+    // The condition we were running towards held
+    Reached,
+    // Ran every tick we were allowed without reaching it
+    Exhausted,
+    // A limit cut the run short
+    Limited(String),
+}
+
+/// A single chip being run a few ticks at a time
+pub struct Session
+{
+    lines: Vec<String>,
+    env: Environment,
+    config: YololConfig,
+    breakpoint: usize,
+
+    tick: usize,
+    last_line: Option<usize>,
+    // What the last button press did
+    status: String,
+}
+
+impl Session
+{
+    pub fn new(lines: Vec<String>, config: YololConfig) -> Result<Self, String>
+    {
+        if lines.is_empty()
+        {
+            return Err("There's no code to debug!".to_owned())
+        }
+
+        if let Some(condition) = &config.stop_condition
+        {
+            execution::validate_condition(condition)?;
+        }
+
+        let breakpoint = config.breakpoint.unwrap_or(1);
+        if breakpoint > lines.len()
+        {
+            return Err(format!("Can't break on line {}, the code only has {} line(s)!", breakpoint, lines.len()))
+        }
+
+        let mut env = Environment::new("Debugger");
+        execution::seed_environment(&mut env, &config.initial_values);
+
+        Ok(Session {
+            lines,
+            env,
+            config,
+            breakpoint,
+
+            tick: 0,
+            last_line: None,
+            status: "Ready to go! Nothing has run yet".to_owned(),
+        })
+    }
+
+    /// Carries out a button press. Stopping is left to whoever owns the session.
+    pub fn apply(&mut self, action: Action)
+    {
+        let tick_limit = self.config.tick_limit;
+
+        self.status = match action
+        {
+            Action::Step(ticks) => match self.run_until(ticks, |_| false)
+            {
+                RunEnd::Limited(reason) => reason,
+                _ => format!("Stepped {} tick(s)", ticks),
+            },
+
+            Action::RunToLine => {
+                let breakpoint = self.breakpoint;
+                match self.run_until(tick_limit, |session| session.next_line() == breakpoint)
+                {
+                    RunEnd::Reached => format!("Ran until line {} was up next", breakpoint),
+                    RunEnd::Exhausted => format!("Didn't get to line {} within {} ticks", breakpoint, tick_limit),
+                    RunEnd::Limited(reason) => reason,
+                }
+            },
+
+            Action::Continue => {
+                let condition = self.config.stop_condition.clone();
+                let result = self.run_until(tick_limit, |session| match &condition
+                {
+                    Some(condition) => execution::check_condition(&session.env, condition),
+                    None => false,
+                });
+
+                match result
+                {
+                    RunEnd::Reached => format!("Stop condition `{}` held", condition.unwrap_or_default()),
+                    RunEnd::Exhausted => format!("Ran the full budget of {} ticks", tick_limit),
+                    RunEnd::Limited(reason) => reason,
+                }
+            },
+
+            Action::Stop => return,
+        };
+    }
+
+    fn next_line(&self) -> usize
+    {
+        execution::next_line(&self.lines, &self.env)
+    }
+
+    // Steps until `done` holds after a tick, up to `limit` ticks
+    fn run_until<F>(&mut self, limit: usize, done: F) -> RunEnd
+    where
+        F: Fn(&Session) -> bool
+    {
+        let deadline = Instant::now() + TIME_LIMIT;
+
+        for _ in 0..limit
+        {
+            self.last_line = Some(execution::step(&self.lines, &mut self.env));
+            self.tick += 1;
+
+            if execution::string_usage(&self.env) > MAX_TOTAL_STRING_LENGTH
+            {
+                return RunEnd::Limited(format!("Stopped, strings grew past {} characters", MAX_TOTAL_STRING_LENGTH))
+            }
+
+            if done(self)
+            {
+                return RunEnd::Reached
+            }
+
+            if Instant::now() > deadline
+            {
+                return RunEnd::Limited(format!("Stopped, hit the {} second time limit", TIME_LIMIT.as_secs()))
+            }
+        }
+
+        RunEnd::Exhausted
+    }
+
+    /// Shows the code with the last and next lines marked, followed by every variable.
+    /// `ended` is the reason the session is over, if it is.
+    pub fn render(&self, ended: Option<&str>) -> String
+    {
+        let next_line = self.next_line();
+
+        let mut header = vec![format!("Tick {} | next line {} | breakpoint line {}", self.tick, next_line, self.breakpoint)];
+        header.push(self.status.clone());
+
+        if !self.env.error.is_empty()
+        {
+            header.push(format!("Line {} errored: {}", self.last_line.unwrap_or(next_line), self.env.error));
+        }
+
+        let code: Vec<String> = self.lines.iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let line_number = index + 1;
+                let marker = if line_number == next_line { '>' } else if Some(line_number) == self.last_line { '*' } else { ' ' };
+                let breakpoint = if line_number == self.breakpoint { '@' } else { ' ' };
+                format!("{}{} {:>2} | {}", marker, breakpoint, line_number, line)
+            })
+            .collect();
+
+        let footer = match ended
+        {
+            Some(reason) => format!("Session over: {}", reason),
+            None => "▶ step, ⏩ step 10, ⏭ run to breakpoint, ⏯ continue, ⏹ stop".to_owned(),
+        };
+
+        let variables: Vec<String> = execution::snapshot(&self.env).into_iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+
+        let layout = |variables: &[String], hidden: usize| {
+            let mut variables = variables.to_vec();
+            if variables.is_empty()
+            {
+                variables.push("nothing set yet".to_owned());
+            }
+            if hidden > 0
+            {
+                variables.push(format!("...and {} more", hidden));
+            }
+
+            format!("{}\n```\n{}\n\nVariables:\n{}\n```\n{}", header.join("\n"), code.join("\n"), variables.join("\n"), footer)
+        };
+
+        // Drop variables off the end until it fits in a message
+        let mut shown = variables.len();
+        loop
+        {
+            let output = layout(&variables[..shown], variables.len() - shown);
+            if output.len() <= MAX_RENDER_LENGTH || shown == 0
+            {
+                return output
+            }
+            shown -= 1;
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+
+use yoloxide::types::VecWindow;
+
+use super::highlight;
+
+// Finding the column takes a tokenize or parse per character, so on lines longer than this we don't try
+const MAX_LOCATED_LENGTH: usize = 256;
+// Room left in a message for the report, with some spare for whatever gets put around it
+const MAX_REPORT_LENGTH: usize = 1800;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Stage
+{
+    Tokenizer,
+    Parser,
+}
+
+impl fmt::Display for Stage
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Stage::Tokenizer => write!(f, "tokenizer"),
+            Stage::Parser => write!(f, "parser"),
+        }
+    }
+}
+
+/// A problem on a single line, located as well as we can manage.
+/// Lines and columns are 1-indexed, columns count characters rather than bytes.
+#[derive(Debug)]
+pub struct Diagnostic
+{
+    pub stage: Stage,
+    pub message: String,
+
+    pub line: usize,
+    pub column: usize,
+    // How many characters past the column the problem spans
+    pub width: usize,
+
+    pub source: String,
+}
+
+impl Diagnostic
+{
+    // Lays out the report around the given rendering of the source line, which has to show the
+    // same characters as the original for the caret to line up
+    fn render(&self, source: &str) -> String
+    {
+        let gutter = " ".repeat(self.line.to_string().len());
+        let marker = format!("^{}", "~".repeat(self.width.saturating_sub(1)));
+
+        format!(
+            "{} failure: {}\n{}--> line {}, col {}\n{} |\n{} | {}\n{} | {}{}",
+            self.stage, self.message,
+            gutter, self.line, self.column,
+            gutter,
+            self.line, source,
+            gutter, " ".repeat(self.column - 1), marker
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{}", self.render(&self.source))
+    }
+}
+
+/// Checks every line on its own so one broken line doesn't hide the rest
+pub fn diagnose(code: &str) -> Vec<Diagnostic>
+{
+    code.lines()
+        .enumerate()
+        .filter_map(|(index, line)| diagnose_line(index + 1, line))
+        .collect()
+}
+
+pub fn diagnose_line(line_number: usize, line: &str) -> Option<Diagnostic>
+{
+    let length = line.chars().count();
+
+    let diagnostic = |stage, message, column: usize| Diagnostic {
+        stage,
+        message,
+        line: line_number,
+        column,
+        width: length.saturating_sub(column - 1).max(1),
+        source: line.to_owned(),
+    };
+
+    let tokens = match yoloxide::tokenizer::tokenize(line.to_owned())
+    {
+        Ok(tokens) => tokens,
+        // Without a column, the whole line gets marked
+        Err(error) if length > MAX_LOCATED_LENGTH => return Some(diagnostic(Stage::Tokenizer, error.to_string(), 1)),
+        Err(error) => {
+            let column = tokenizer_column(line);
+            let mut diagnostic = diagnostic(Stage::Tokenizer, error.to_string(), column);
+            diagnostic.width = 1;
+            return Some(diagnostic)
+        }
+    };
+
+    let mut window = VecWindow::new(tokens, 0);
+    match yoloxide::parser::parse_program(&mut window)
+    {
+        Ok(_) => None,
+        Err(error) if length > MAX_LOCATED_LENGTH => Some(diagnostic(Stage::Parser, error.to_string(), 1)),
+        Err(error) => Some(diagnostic(Stage::Parser, error.to_string(), parser_column(line))),
+    }
+}
+
+// Byte offsets of every character boundary in the line, including the very end
+fn boundaries(line: &str) -> Vec<usize>
+{
+    line.char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(line.len()))
+        .collect()
+}
+
+// yoloxide errors don't say where they happened. The longest prefix that still tokenizes
+// tells us where the tokenizer gave up, so the character right after it is the culprit.
+fn tokenizer_column(line: &str) -> usize
+{
+    let boundaries = boundaries(line);
+
+    let good_chars = boundaries.iter()
+        .rposition(|&end| yoloxide::tokenizer::tokenize(line[..end].to_owned()).is_ok())
+        .unwrap_or(0);
+
+    (good_chars + 1).min(line.chars().count().max(1))
+}
+
+// Same idea as the tokenizer, but prefixes cut mid-expression never parse. Cutting at spaces
+// finds the last complete statement, and the problem starts with whatever comes after it.
+fn parser_column(line: &str) -> usize
+{
+    let parses = |prefix: &str| {
+        match yoloxide::tokenizer::tokenize(prefix.to_owned())
+        {
+            Ok(tokens) => yoloxide::parser::parse_program(&mut VecWindow::new(tokens, 0)).is_ok(),
+            Err(_) => false,
+        }
+    };
+
+    let last_good = line.char_indices()
+        .rev()
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(offset, _)| offset)
+        .find(|&offset| !line[..offset].trim().is_empty() && parses(&line[..offset]));
+
+    match last_good
+    {
+        Some(offset) => {
+            // Skip over the whitespace to land on the start of the broken statement
+            let skipped = line[offset..].len() - line[offset..].trim_start().len();
+            line[..offset + skipped].chars().count() + 1
+        },
+        None => line.chars().take_while(|c| c.is_whitespace()).count() + 1,
+    }
+}
+
+/// Formats a list of diagnostics for a message, or returns `None` if there weren't any.
+/// Only as many as fit in a message are shown, the rest just get counted.
+pub fn report(diagnostics: &[Diagnostic]) -> Option<String>
+{
+    let first = diagnostics.first()?;
+
+    let mut reports: Vec<String> = vec![];
+    let mut length = 0;
+
+    for diagnostic in diagnostics
+    {
+        // Highlighting adds a lot of escape codes, so a line that doesn't fit with them might still fit without
+        let highlighted = diagnostic.render(&highlight::highlight_line(&diagnostic.source));
+        let rendered = if length + highlighted.len() <= MAX_REPORT_LENGTH { highlighted } else { diagnostic.to_string() };
+
+        if length + rendered.len() > MAX_REPORT_LENGTH
+        {
+            break
+        }
+
+        length += rendered.len();
+        reports.push(rendered);
+    }
+
+    if reports.is_empty()
+    {
+        return Some(format!("Found {} broken line(s), but they're too long to show here! The first is line {}: {}", diagnostics.len(), first.line, first.message))
+    }
+
+    let hidden = diagnostics.len() - reports.len();
+    if hidden > 0
+    {
+        reports.push(format!("...and {} more", hidden));
+    }
+
+    Some(format!("Found {} broken line(s): ```ansi\n{}\n```", diagnostics.len(), reports.join("\n\n")))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parser_column_points_past_the_last_good_statement()
+    {
+        assert_eq!(parser_column("a=1 b=(2"), 5);
+        assert_eq!(parser_column("a=1 b=2   c=)"), 11);
+    }
+
+    #[test]
+    fn parser_column_skips_leading_whitespace()
+    {
+        assert_eq!(parser_column("  a=(1"), 3);
+    }
+
+    #[test]
+    fn huge_lines_are_not_located()
+    {
+        let line = format!("a=(1 {}", "b=2 ".repeat(100));
+        let diagnostic = diagnose_line(1, &line).expect("Broken line wasn't diagnosed!");
+        assert_eq!(diagnostic.column, 1);
+    }
+
+    #[test]
+    fn report_fits_in_a_message()
+    {
+        let code = vec!["a=(1 b=2 c=3 d=4 e=5 f=6 g=7 h=8 i=9 j=10"; 30].join("\n");
+        let report = report(&diagnose(&code)).expect("Broken code had no report!");
+
+        assert!(report.len() <= 2000);
+        assert!(report.contains("more"));
+    }
+}
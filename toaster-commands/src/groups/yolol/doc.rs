@@ -0,0 +1,238 @@
+pub struct Entry
+{
+    pub topic: &'static str,
+    // Other words people search for this by, including the operators themselves
+    pub keywords: &'static [&'static str],
+    pub text: &'static str,
+    // Runs once through, one tick per line, so it should end with something worth looking at
+    pub example: Option<&'static str>,
+}
+
+pub const ENTRIES: &[Entry] = &[
+    Entry {
+        topic: "arithmetic",
+        keywords: &["math", "+", "-", "*", "/", "%", "^", "plus", "minus", "times", "divide", "modulo", "power"],
+        text: "`a + b`, `a - b`, `a * b` and `a / b` work like you'd expect. `a % b` is the remainder of `a / b` and `a ^ b` raises `a` to the power of `b`. \
+Dividing by zero is a runtime error, which skips the rest of the line.",
+        example: Some("a = 7 + 3 * 2\nb = 7 % 3\nc = 2 ^ 10\nd = 10 / 4"),
+    },
+    Entry {
+        topic: "numbers",
+        keywords: &["decimal", "precision", "fixed point", "rounding"],
+        text: "Numbers are fixed point with three decimal places, so anything smaller than `0.001` is lost. \
+There are no separate integers or floats, and results are cut off rather than rounded.",
+        example: Some("a = 1 / 3\nb = a * 3\nc = 0.0004 + 0.0004"),
+    },
+    Entry {
+        topic: "strings",
+        keywords: &["text", "string", "concatenate", "quotes"],
+        text: "Strings go in double quotes and have no escape characters. `+` glues two strings together, or a string and a number. \
+`a - b` removes the last place `b` shows up in `a`. Anything else you try on a string is a runtime error.",
+        example: Some("a = \"hello\" + \" \" + \"world\"\nb = a - \"world\"\nc = \"value: \" + 5"),
+    },
+    Entry {
+        topic: "comparisons",
+        keywords: &["==", "!=", "<", ">", "<=", ">=", "equals", "compare"],
+        text: "`==`, `!=`, `<`, `>`, `<=` and `>=` give `1` when they hold and `0` when they don't. \
+Strings can be compared with `==` and `!=`.",
+        example: Some("a = 3 > 2\nb = 3 == 2\nc = \"on\" == \"on\""),
+    },
+    Entry {
+        topic: "logic",
+        keywords: &["and", "or", "not", "boolean", "true", "false"],
+        text: "`0` counts as false and every other number counts as true. `a and b`, `a or b` and `not a` give `1` or `0`. \
+Both sides are always worked out, there's no short circuiting.",
+        example: Some("a = 1 and 0\nb = 1 or 0\nc = not 5"),
+    },
+    Entry {
+        topic: "increments",
+        keywords: &["++", "--", "increment", "decrement"],
+        text: "`a++` and `++a` add 1 to `a`, `a--` and `--a` take 1 away. As part of a bigger expression, the prefix form gives the new value and the postfix form gives the old one. \
+On a string, `++` adds a space to the end and `--` removes the last character.",
+        example: Some("a = 5\nb = a++\nc = ++a\ns = \"abc\" s--"),
+    },
+    Entry {
+        topic: "assignment",
+        keywords: &["=", "+=", "-=", "*=", "/=", "%=", "set", "variable"],
+        text: "`a = b` sets `a`. The shorthand `a += b`, `a -= b`, `a *= b`, `a /= b` and `a %= b` do the operation and store the result back in `a`. \
+Names are case insensitive, and a variable nobody has set yet is `0`.",
+        example: Some("a = 10\na += 5\na *= 2\nb = never_set"),
+    },
+    Entry {
+        topic: "functions",
+        keywords: &["abs", "sqrt", "sin", "cos", "tan", "asin", "acos", "atan", "factorial", "!", "trig"],
+        text: "`abs a`, `sqrt a`, `sin a`, `cos a`, `tan a`, `asin a`, `acos a` and `atan a` are written like operators, without parentheses. \
+The trig functions work in degrees. `a!` is the factorial of `a`.",
+        example: Some("a = abs -4\nb = sqrt 16\nc = sin 90\nd = 4!"),
+    },
+    Entry {
+        topic: "precedence",
+        keywords: &["order", "order of operations", "parentheses", "brackets"],
+        text: "From tightest to loosest: parentheses, `++` and `--` on a variable, negation `-`, the keyword operators `abs` `sqrt` `sin` `cos` `tan` `asin` `acos` `atan` and `not`, \
+postfix `!`, `^` (grouping right to left), then `*` `/` `%`, then `+` `-`, then `<` `>` `<=` `>=`, then `==` `!=`, then `or`, and loosest of all `and`. \
+Since `not` binds so tightly, `not a == b` means `(not a) == b`. When in doubt, add parentheses, they only cost characters.",
+        example: Some("a = 2 + 3 * 4\nb = (2 + 3) * 4\nc = not 0 == 0\nd = 1 or 0 and 0"),
+    },
+    Entry {
+        topic: "if",
+        keywords: &["then", "else", "end", "conditional", "branch"],
+        text: "`if <condition> then <statements> else <statements> end` has to fit on one line. The `else` part is optional and ifs can be nested. \
+The condition counts as true when it's anything other than `0`.",
+        example: Some("a = 3\nif a > 2 then b = \"big\" else b = \"small\" end"),
+    },
+    Entry {
+        topic: "goto",
+        keywords: &["jump", "loop", "line"],
+        text: "`goto <line>` jumps to a line, numbered from 1, and nothing after it on the same line runs. \
+The line number can be worked out at runtime, like `goto 2 + a`. It's rounded down and kept between lines 1 and 20, and here, jumping past the end of a shorter program goes back to line 1.",
+        example: Some("goto 3\na = \"skipped\"\nb = \"landed on line 3\""),
+    },
+    Entry {
+        topic: "globals",
+        keywords: &[":", "fields", "device", "network", "global", "local"],
+        text: "Names starting with `:` are device fields on the chip's data network, shared with every device and chip on it. \
+Names without `:` are local to the chip. A field can be renamed in its device's settings, and `:name` refers to whatever it's called there.",
+        example: Some(":Output = 1\nlocal = :Output + 1"),
+    },
+    Entry {
+        topic: "devices",
+        keywords: &["button", "lamp", "light", "panel", "display", "ButtonState", "LampOn", "fields"],
+        text: "Some common fields: buttons have `ButtonState`, `ButtonOnStateValue`, `ButtonOffStateValue` and `ButtonStyle`. \
+Lamps have `LampOn`, `LampLumens`, `LampRange`, `LampColorHue`, `LampColorSaturation` and `LampColorValue`. Text panels show `PanelValue`. \
+Chips have `ChipWait`, which pauses the chip for that many ticks. The bot doesn't simulate any devices, so use `--set` to fake their fields.",
+        example: Some(":ButtonState = 1\nif :ButtonState then :LampOn = 1 end"),
+    },
+    Entry {
+        topic: "chips",
+        keywords: &["tiers", "basic", "advanced", "professional", "limits", "chip tier"],
+        text: "Every chip has 20 lines of 70 characters. A basic chip has arithmetic with `+ - * /`, comparisons, `and`, `or`, `not`, increments and assignments. \
+An advanced chip adds `%`, `^`, `abs`, `sqrt` and `!`. A professional chip adds the trig functions.",
+        example: None,
+    },
+    Entry {
+        topic: "ticks",
+        keywords: &["tick", "timing", "speed", "execution", "errors", "runtime error"],
+        text: "A chip runs one line every tick, which is 0.2 seconds, then moves to the next line and wraps around after the last one. \
+A runtime error stops the rest of that line, but the chip carries on from the next line. Chips on the same network all run at once.",
+        example: Some("a = 1 b = 1 / 0 c = 1\nd = \"still running\""),
+    },
+];
+
+/// Edit distance between two strings, counting insertions, deletions and substitutions
+fn distance(a: &str, b: &str) -> usize
+{
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate()
+    {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate()
+        {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// Lower is better. Exact matches beat prefixes, which beat typos
+fn score(entry: &Entry, query: &str) -> Option<usize>
+{
+    std::iter::once(entry.topic)
+        .chain(entry.keywords.iter().cloned())
+        .filter_map(|name| {
+            let name = name.to_lowercase();
+
+            if name == query
+            {
+                Some(0)
+            }
+            else if query.len() >= 3 && name.starts_with(query)
+            {
+                Some(1)
+            }
+            else
+            {
+                // Symbols are too short for typos to mean anything
+                let allowed = query.chars().filter(|c| c.is_alphanumeric()).count() / 4;
+                let distance = distance(&name, query);
+
+                if distance <= allowed { Some(distance + 1) } else { None }
+            }
+        })
+        .min()
+}
+
+/// Every entry that matches the query, best first
+pub fn search(query: &str) -> Vec<&'static Entry>
+{
+    let query = query.trim().to_lowercase();
+
+    let mut matches: Vec<(usize, &'static Entry)> = ENTRIES.iter()
+        .filter_map(|entry| score(entry, &query).map(|score| (score, entry)))
+        .collect();
+
+    // Stable, so ties stay in the order they're written in
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// How many ticks an entry's example needs to run through once
+pub fn example_ticks(example: &str) -> usize
+{
+    example.lines().count()
+}
+
+pub fn topics() -> String
+{
+    ENTRIES.iter().map(|entry| format!("`{}`", entry.topic)).collect::<Vec<String>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn entry(topic: &str) -> &'static Entry
+    {
+        ENTRIES.iter().find(|entry| entry.topic == topic).expect("No entry with that topic!")
+    }
+
+    #[test]
+    fn distance_counts_edits()
+    {
+        assert_eq!(distance("goto", "goto"), 0);
+        assert_eq!(distance("goto", "gotp"), 1);
+        assert_eq!(distance("goto", "got"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn exact_matches_beat_prefixes_and_typos()
+    {
+        assert_eq!(score(entry("strings"), "strings"), Some(0));
+        assert_eq!(score(entry("strings"), "stri"), Some(1));
+        assert_eq!(score(entry("comparisons"), "comparisins"), Some(2));
+    }
+
+    #[test]
+    fn short_queries_need_to_be_exact()
+    {
+        assert_eq!(score(entry("goto"), "go"), None);
+        assert_eq!(score(entry("comparisons"), "=="), Some(0));
+        assert_eq!(score(entry("comparisons"), "="), None);
+    }
+
+    #[test]
+    fn search_puts_the_best_match_first()
+    {
+        assert_eq!(search("GOTO").first().map(|entry| entry.topic), Some("goto"));
+        assert!(search("xyzzy").is_empty());
+    }
+}
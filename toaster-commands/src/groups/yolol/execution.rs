@@ -0,0 +1,366 @@
+use std::fmt;
+use std::sync::mpsc;
+use std::convert::TryInto;
+use std::collections::{
+    BTreeMap,
+    HashMap,
+    HashSet,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use serde::Deserialize;
+
+use yoloxide::{
+    environment::{
+        Environment,
+        ContextMap,
+    },
+    types::{
+        VecWindow,
+        ast::value::LiteralValue,
+    },
+};
+
+use toaster_core::plugin_tasks;
+
+use super::config::YololConfig;
+
+// Limits to stop pathological programs from stalling the bot or eating all its memory
+pub const TIME_LIMIT: Duration = Duration::from_secs(5);
+pub const MAX_TOTAL_STRING_LENGTH: usize = 64 * 1024;
+
+// How long past the time limit we wait on a worker before giving up on it. The limit is only
+// checked between ticks, so this covers a single tick that takes forever.
+pub const WORKER_GRACE: Duration = Duration::from_secs(2);
+
+/// Why a run of `output_execution` came to an end
+#[derive(Debug, Clone, Copy)]
+pub enum StopReason
+{
+    // The whole tick budget was used up
+    TickLimit(usize),
+    // The `--until` condition held after the given tick
+    Condition(usize),
+    // The program stopped changing anything as of the given tick
+    Settled(usize),
+    // Ran out of wall-clock time after the given tick
+    TimedOut(usize),
+    // The strings stored in the environment got too big after the given tick
+    MemoryLimit(usize),
+}
+
+impl fmt::Display for StopReason
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            StopReason::TickLimit(ticks) => write!(f, "ran the full budget of {} ticks", ticks),
+            StopReason::Condition(tick) => write!(f, "stop condition held after tick {}", tick),
+            StopReason::Settled(tick) => write!(f, "program settled into a fixed state after tick {}", tick),
+            StopReason::TimedOut(tick) => write!(f, "hit the {} second time limit after tick {}, these are partial results", TIME_LIMIT.as_secs(), tick),
+            StopReason::MemoryLimit(tick) => write!(f, "strings grew past {} characters after tick {}, these are partial results", MAX_TOTAL_STRING_LENGTH, tick),
+        }
+    }
+}
+
+/// A flattened view of every variable in an environment, keyed by name.
+/// Globals are always keyed with their leading `:` so they can't collide with locals.
+pub type Snapshot = BTreeMap<String, String>;
+
+pub fn snapshot(env: &Environment) -> Snapshot
+{
+    let contexts = contexts(env);
+    snapshot_of(&contexts.locals, &contexts.globals)
+}
+
+/// Same as `snapshot`, for contexts that have already been read out of an environment
+fn snapshot_of(locals: &HashMap<String, LiteralValue>, globals: &HashMap<String, LiteralValue>) -> Snapshot
+{
+    let locals = locals.iter()
+        .map(|(name, value)| (name.clone(), value.to_string()));
+
+    let globals = globals.iter()
+        .map(|(name, value)| (format!(":{}", name.trim_start_matches(':')), value.to_string()));
+
+    locals.chain(globals).collect()
+}
+
+/// Every variable a chip can see, split into its own locals and the globals on its network.
+/// Globals are keyed with their leading `:`, the same way yoloxide stores them.
+#[derive(Deserialize)]
+pub struct Contexts
+{
+    #[serde(rename = "local_context")]
+    pub locals: HashMap<String, LiteralValue>,
+    #[serde(rename = "global_context")]
+    pub globals: HashMap<String, LiteralValue>,
+}
+
+// yoloxide keeps the contexts private, but environments serialize, so that's how we read them
+pub fn contexts(env: &Environment) -> Contexts
+{
+    serde_json::to_value(env)
+        .and_then(serde_json::from_value)
+        .expect("Environment contexts failed to round trip through json!")
+}
+
+/// Writes every one of the given globals into the environment
+pub fn set_globals(env: &mut Environment, globals: &HashMap<String, LiteralValue>)
+{
+    for (name, value) in globals
+    {
+        env.set_val(name.clone(), value.clone());
+    }
+}
+
+fn string_length(context: &HashMap<String, LiteralValue>) -> usize
+{
+    context.values()
+        .map(|value| match value
+        {
+            LiteralValue::StringVal(string) => string.len(),
+            _ => 0
+        })
+        .sum()
+}
+
+// yoloxide doesn't expose an expression evaluator, so conditions get wrapped into an if/goto
+// line and run on a scratch copy of the environment. Where it jumps tells us the result.
+const CONDITION_TRUE_LINE: i64 = 7;
+
+fn condition_line(condition: &str) -> String
+{
+    format!("if {} then goto {} else goto {} end", condition, CONDITION_TRUE_LINE, CONDITION_TRUE_LINE + 2)
+}
+
+/// Makes sure a stop condition is valid yolol before we start running with it
+pub fn validate_condition(condition: &str) -> Result<(), String>
+{
+    let tokens = yoloxide::tokenizer::tokenize(condition_line(condition))
+        .map_err(|error| format!("Couldn't tokenize stop condition `{}`: ```{}```", condition, error))?;
+
+    let mut window = VecWindow::new(tokens, 0);
+    yoloxide::parser::parse_program(&mut window)
+        .map_err(|error| format!("Couldn't parse stop condition `{}`: ```{}```", condition, error))?;
+
+    Ok(())
+}
+
+pub fn check_condition(env: &Environment, condition: &str) -> bool
+{
+    let mut scratch = env.clone();
+    scratch.next_line = 1;
+
+    yoloxide::execute_line(&mut scratch, condition_line(condition));
+
+    // A runtime error in the condition leaves us on line 2, which counts as false
+    scratch.next_line == CONDITION_TRUE_LINE
+}
+
+/// Sets each of the given variables by running an assignment for it.
+/// Running a line moves the chip along, so the line it was on gets put back afterwards.
+pub fn seed_environment(env: &mut Environment, initial_values: &[(String, String)])
+{
+    let next_line = env.next_line;
+
+    for (name, value) in initial_values
+    {
+        yoloxide::execute_line(env, format!("{}={}", name, value));
+    }
+
+    env.next_line = next_line;
+}
+
+/// The 1-indexed line a chip will run next. Lines past either end wrap back to line 1.
+pub fn next_line(lines: &[String], env: &Environment) -> usize
+{
+    let line_len: i64 = lines.len().try_into().unwrap();
+
+    // This is a stupid line but I can't find a better way to do it for some reason...
+    let next_line = if env.next_line > line_len || env.next_line <= 0 { 1 } else { env.next_line };
+    next_line.try_into().unwrap()
+}
+
+/// Runs a single line of a chip, returning which line that was. `lines` can't be empty.
+pub fn step(lines: &[String], env: &mut Environment) -> usize
+{
+    let line = next_line(lines, env);
+    env.next_line = line as i64;
+
+    // Clear out the last error, so it always belongs to the line that just ran
+    env.error.clear();
+    yoloxide::execute_line(env, lines[line - 1].clone());
+
+    line
+}
+
+/// Total length of every string a chip can see
+pub fn string_usage(env: &Environment) -> usize
+{
+    let contexts = contexts(env);
+    string_length(&contexts.locals) + string_length(&contexts.globals)
+}
+
+/// Runs the lines of a chip until the tick budget or one of the configured stop conditions ends it.
+/// After every tick, `on_tick` gets the tick number, the line that just ran and the updated environment.
+pub fn run<F>(lines: &[String], config: &YololConfig, env: &mut Environment, mut on_tick: F) -> Result<StopReason, String>
+where
+    F: FnMut(usize, usize, &Environment)
+{
+    let chips = [lines.to_vec()];
+    run_network(&chips, config, std::slice::from_mut(env), |tick, _, line, env| on_tick(tick, line, env))
+}
+
+/// Runs several chips in lockstep. Every tick, each chip runs one line in order, and all of them
+/// share the same global fields. Stop conditions are checked against the first chip.
+/// `on_tick` additionally gets the index of the chip that just ran.
+pub fn run_network<F>(chips: &[Vec<String>], config: &YololConfig, envs: &mut [Environment], on_tick: F) -> Result<StopReason, String>
+where
+    F: FnMut(usize, usize, usize, &Environment)
+{
+    run_network_until(chips, config, envs, Instant::now() + TIME_LIMIT, on_tick)
+}
+
+/// Same as `run_network`, but stops at the given deadline instead of a full time limit from now.
+/// Lets several runs share one time limit between them.
+pub fn run_network_until<F>(chips: &[Vec<String>], config: &YololConfig, envs: &mut [Environment], deadline: Instant, mut on_tick: F) -> Result<StopReason, String>
+where
+    F: FnMut(usize, usize, usize, &Environment)
+{
+    if let Some(condition) = &config.stop_condition
+    {
+        validate_condition(condition)?;
+    }
+
+    if let Some(index) = chips.iter().position(|lines| lines.is_empty())
+    {
+        return Err(format!("There's no code to run on chip {}!", index + 1))
+    }
+
+    for env in envs.iter_mut()
+    {
+        seed_environment(env, &config.initial_values);
+    }
+
+    // Reading the contexts means serializing the whole environment, so each chip's are read once per tick,
+    // right after it runs. Its locals can't change again until its next turn, and the globals get passed along.
+    let mut locals = vec![];
+    let mut globals = HashMap::new();
+    for env in envs.iter()
+    {
+        let contexts = contexts(env);
+        locals.push(contexts.locals);
+        // Start with every global field any of the chips already knows about
+        globals.extend(contexts.globals);
+    }
+
+    // Lines run since the last time any variable changed. Since execution is deterministic,
+    // revisiting one of them without a change in between means we're stuck in a fixed loop.
+    let mut visited_since_change = HashSet::new();
+    let mut last_snapshots: Vec<Snapshot> = locals.iter().map(|locals| snapshot_of(locals, &globals)).collect();
+
+    for tick in 1..=config.tick_limit
+    {
+        let mut next_lines = vec![];
+
+        for (index, (lines, env)) in chips.iter().zip(envs.iter_mut()).enumerate()
+        {
+            // Globals are never removed, so writing all of them in is enough to catch a chip up
+            set_globals(env, &globals);
+            let line = step(lines, env);
+
+            let contexts = contexts(env);
+            locals[index] = contexts.locals;
+            globals = contexts.globals;
+
+            next_lines.push(line);
+            on_tick(tick, index, line, env);
+        }
+
+        // Writes from later chips in the tick need to be seen by the earlier ones too.
+        // The last chip to run already has all of them.
+        if let Some((_, earlier)) = envs.split_last_mut()
+        {
+            for env in earlier
+            {
+                set_globals(env, &globals);
+            }
+        }
+
+        let total_string_length = string_length(&globals) + locals.iter().map(string_length).sum::<usize>();
+        if total_string_length > MAX_TOTAL_STRING_LENGTH
+        {
+            return Ok(StopReason::MemoryLimit(tick))
+        }
+
+        if Instant::now() > deadline
+        {
+            return Ok(StopReason::TimedOut(tick))
+        }
+
+        if config.stop_on_settle
+        {
+            if !visited_since_change.insert(next_lines)
+            {
+                return Ok(StopReason::Settled(tick - 1))
+            }
+
+            let current_snapshots: Vec<Snapshot> = locals.iter().map(|locals| snapshot_of(locals, &globals)).collect();
+            if current_snapshots != last_snapshots
+            {
+                visited_since_change.clear();
+                last_snapshots = current_snapshots;
+            }
+        }
+
+        if let Some(condition) = &config.stop_condition
+        {
+            if check_condition(&envs[0], condition)
+            {
+                return Ok(StopReason::Condition(tick))
+            }
+        }
+    }
+
+    Ok(StopReason::TickLimit(config.tick_limit))
+}
+
+/// Runs some work on its own thread, so a program that takes forever can't hold up whoever called us.
+/// The work should stop itself at the time limit, anything that runs much longer is given up on.
+pub fn sandboxed<T, F>(work: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static
+{
+    let (sender, receiver) = mpsc::channel();
+
+    // A worker we gave up on keeps running, so it has to keep this library loaded until it's done
+    let task = Box::new(move || {
+        // If we took too long the receiver is gone and nobody wants the result anyway
+        let _ = sender.send(work());
+    });
+
+    plugin_tasks::spawn(super::YOLOL_GROUP.name, "yolol-worker".to_owned(), task)
+        .map_err(|error| format!("Couldn't start a worker to run your code on! Error: ```{}```", error))?;
+
+    match receiver.recv_timeout(TIME_LIMIT + WORKER_GRACE)
+    {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(format!("Execution got stuck for over {} seconds, so I gave up on it!", (TIME_LIMIT + WORKER_GRACE).as_secs())),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err("Execution crashed before it could finish! That's probably a yoloxide bug...".to_owned()),
+    }
+}
+
+/// Runs a network on a worker thread. The environments come back with whatever state they ended up in,
+/// even when a limit stopped the run early.
+pub fn run_network_sandboxed(chips: Vec<Vec<String>>, config: YololConfig, mut envs: Vec<Environment>) -> Result<(StopReason, Vec<Environment>), String>
+{
+    sandboxed(move || {
+        let stop_reason = run_network(&chips, &config, &mut envs, |_, _, _, _| ())?;
+        Ok((stop_reason, envs))
+    })
+}
@@ -0,0 +1,201 @@
+use cylon_ast::{
+    CylonProg,
+    CylonStat,
+    CylonExpr,
+};
+
+use super::minify::expression_string;
+use super::syntax::{
+    UnaryForm,
+    is_decrement,
+    is_increment,
+    strip_groups,
+    unary_form,
+};
+
+/// Describes every line of a program in plain English, one sentence per line
+pub fn explain(prog: &CylonProg) -> Vec<String>
+{
+    prog.lines.iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let mut sentence = match line.code.as_slice()
+            {
+                [] => "Does nothing".to_owned(),
+                stats => capitalize(&stats.iter().map(describe_stat).collect::<Vec<String>>().join(", then ")),
+            };
+
+            let comment = line.comment.trim_start_matches("//").trim();
+            if !comment.is_empty()
+            {
+                sentence += &format!(" (comment: \"{}\")", comment);
+            }
+
+            format!("Line {}: {}.", index + 1, sentence)
+        })
+        .collect()
+}
+
+fn capitalize(text: &str) -> String
+{
+    let mut chars = text.chars();
+    match chars.next()
+    {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn describe_stats(stats: &[CylonStat]) -> String
+{
+    match stats
+    {
+        [] => "do nothing".to_owned(),
+        stats => stats.iter().map(describe_stat).collect::<Vec<String>>().join(" and "),
+    }
+}
+
+fn describe_stat(stat: &CylonStat) -> String
+{
+    match stat
+    {
+        CylonStat::Goto { expression } => match strip_groups(expression)
+        {
+            CylonExpr::Number { num } => format!("jump to line {}", num),
+            expression => format!("jump to the line given by `{}`", expression_string(expression)),
+        },
+        CylonStat::If { condition, body, else_body } => {
+            let mut sentence = format!("if {}, {}", describe_expr(condition), describe_stats(body));
+
+            if !else_body.is_empty()
+            {
+                sentence += &format!(", otherwise {}", describe_stats(else_body));
+            }
+
+            sentence
+        },
+        CylonStat::Assignment { identifier, operator, value } => {
+            let target = describe_name(identifier);
+            let value = describe_expr(value);
+
+            match operator.as_str()
+            {
+                "+=" => format!("add {} to {}", value, target),
+                "-=" => format!("subtract {} from {}", value, target),
+                "*=" => format!("multiply {} by {}", target, value),
+                "/=" => format!("divide {} by {}", target, value),
+                "%=" => format!("set {} to the remainder of dividing it by {}", target, value),
+                _ => format!("set {} to {}", target, value),
+            }
+        },
+        CylonStat::Expression { expression } => match strip_groups(expression)
+        {
+            CylonExpr::UnaryOp { operator, operand } if is_increment(operator) => format!("increase {} by 1", describe_expr(operand)),
+            CylonExpr::UnaryOp { operator, operand } if is_decrement(operator) => format!("decrease {} by 1", describe_expr(operand)),
+            expression => format!("work out `{}` and throw the result away", expression_string(expression)),
+        },
+    }
+}
+
+// Globals are device fields, which is how most people will think of them
+fn describe_name(name: &str) -> String
+{
+    let field = name.trim_start_matches(':');
+    if field.len() < name.len()
+    {
+        format!("the {} field", field)
+    }
+    else
+    {
+        name.to_owned()
+    }
+}
+
+fn binary_words(operator: &str) -> Option<&'static str>
+{
+    let words = match operator
+    {
+        "+" => "plus",
+        "-" => "minus",
+        "*" => "times",
+        "/" => "divided by",
+        "%" => "modulo",
+        "^" => "to the power of",
+        "==" => "equals",
+        "!=" => "doesn't equal",
+        "<" => "is less than",
+        ">" => "is greater than",
+        "<=" => "is at most",
+        ">=" => "is at least",
+        "and" => "and",
+        "or" => "or",
+        _ => return None,
+    };
+
+    Some(words)
+}
+
+fn unary_words(operator: &str) -> Option<&'static str>
+{
+    let words = match operator
+    {
+        "-" => "negative",
+        "not" => "not",
+        "abs" => "the absolute value of",
+        "sqrt" => "the square root of",
+        "sin" => "the sine in degrees of",
+        "cos" => "the cosine in degrees of",
+        "tan" => "the tangent in degrees of",
+        "asin" | "arcsin" => "the arcsine in degrees of",
+        "acos" | "arccos" => "the arccosine in degrees of",
+        "atan" | "arctan" => "the arctangent in degrees of",
+        _ => return None,
+    };
+
+    Some(words)
+}
+
+// Only simple operands get spelled out. Anything nested is left as code, since English
+// has no parentheses and "a plus b times c" could mean either thing.
+fn describe_operand(expr: &CylonExpr) -> String
+{
+    match strip_groups(expr)
+    {
+        CylonExpr::Number { .. } |
+        CylonExpr::String { .. } |
+        CylonExpr::Identifier { .. } => describe_expr(expr),
+        expr => format!("`{}`", expression_string(expr)),
+    }
+}
+
+fn describe_expr(expr: &CylonExpr) -> String
+{
+    match strip_groups(expr)
+    {
+        CylonExpr::Number { num } => num.clone(),
+        CylonExpr::String { str } => format!("\"{}\"", str),
+        CylonExpr::Identifier { name } => describe_name(name),
+
+        CylonExpr::BinaryOp { operator, left, right } => match binary_words(operator)
+        {
+            Some(words) => format!("{} {} {}", describe_operand(left), words, describe_operand(right)),
+            None => format!("`{}`", expression_string(expr)),
+        },
+
+        CylonExpr::UnaryOp { operator, operand } => match unary_form(operator)
+        {
+            UnaryForm::Postfix("!") => format!("the factorial of {}", describe_operand(operand)),
+            UnaryForm::Prefix("++") => format!("{} after increasing it by 1", describe_operand(operand)),
+            UnaryForm::Prefix("--") => format!("{} after decreasing it by 1", describe_operand(operand)),
+            UnaryForm::Postfix("++") => format!("{} before increasing it by 1", describe_operand(operand)),
+            UnaryForm::Postfix("--") => format!("{} before decreasing it by 1", describe_operand(operand)),
+            _ => match unary_words(operator)
+            {
+                Some(words) => format!("{} {}", words, describe_operand(operand)),
+                None => format!("`{}`", expression_string(expr)),
+            },
+        },
+
+        CylonExpr::Group { .. } => unreachable!("Groups were already stripped"),
+    }
+}
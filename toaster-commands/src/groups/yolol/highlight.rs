@@ -0,0 +1,142 @@
+use yoloxide::types::Token;
+
+use super::syntax::is_word_char;
+
+// Discord only understands the basic ANSI colors inside ```ansi blocks
+const RESET: &str = "\u{1b}[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind
+{
+    Keyword,
+    Global,
+    Local,
+    String,
+    Number,
+    Comment,
+    Plain,
+}
+
+impl Kind
+{
+    fn color(self) -> Option<&'static str>
+    {
+        match self
+        {
+            Kind::Keyword => Some("\u{1b}[1;34m"),
+            Kind::Global => Some("\u{1b}[36m"),
+            Kind::String => Some("\u{1b}[32m"),
+            Kind::Number => Some("\u{1b}[33m"),
+            Kind::Comment => Some("\u{1b}[30m"),
+            Kind::Local |
+            Kind::Plain => None,
+        }
+    }
+}
+
+/// Colors code for a ```` ```ansi ```` block. The text itself is left exactly as it was, so
+/// anything lined up against it, like a caret under an error, still points at the right spot.
+pub fn highlight(code: &str) -> String
+{
+    code.lines()
+        .map(highlight_line)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn highlight_line(line: &str) -> String
+{
+    let mut output = String::new();
+
+    for lexeme in lexemes(line)
+    {
+        match classify(lexeme).color()
+        {
+            Some(color) => {
+                output += color;
+                output += lexeme;
+                output += RESET;
+            },
+            None => output += lexeme,
+        }
+    }
+
+    output
+}
+
+/// Wraps code in an ansi block if it still fits in a message once colored, otherwise a plain one
+pub fn code_block(code: &str, limit: usize) -> String
+{
+    let highlighted = highlight(code);
+
+    if highlighted.len() + "```ansi\n\n```".len() <= limit
+    {
+        format!("```ansi\n{}\n```", highlighted)
+    }
+    else
+    {
+        format!("```{}```", code)
+    }
+}
+
+// Asks the tokenizer what a single piece of code is. Anything it can't make sense of is left plain,
+// which keeps broken code from error messages readable.
+fn classify(lexeme: &str) -> Kind
+{
+    let first = match lexeme.chars().next()
+    {
+        Some(first) if !first.is_whitespace() => first,
+        _ => return Kind::Plain,
+    };
+
+    let tokens = match yoloxide::tokenizer::tokenize(lexeme.to_owned())
+    {
+        Ok(tokens) => tokens,
+        Err(_) => return Kind::Plain,
+    };
+
+    match tokens.first()
+    {
+        Some(Token::Comment(_)) => Kind::Comment,
+        Some(Token::StringToken(_)) => Kind::String,
+        Some(Token::YololNum(_)) => Kind::Number,
+        Some(Token::Identifier(_)) if first == ':' => Kind::Global,
+        Some(Token::Identifier(_)) => Kind::Local,
+        Some(_) if first == ':' => Kind::Global,
+        // Any other word the tokenizer gave its own token is a keyword or a built in operator
+        Some(_) if first.is_alphabetic() => Kind::Keyword,
+        _ => Kind::Plain,
+    }
+}
+
+// Splits a line into pieces the tokenizer would see as one token each, plus the whitespace between them
+fn lexemes(line: &str) -> Vec<&str>
+{
+    let mut lexemes = vec![];
+    let mut rest = line;
+
+    while let Some(first) = rest.chars().next()
+    {
+        let length = if rest.starts_with("//") {
+            rest.len()
+        }
+        else if first == '"' {
+            // Unterminated strings run to the end of the line
+            rest[1..].find('"').map(|end| end + 2).unwrap_or(rest.len())
+        }
+        else if first.is_whitespace() {
+            rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len())
+        }
+        else if is_word_char(first) {
+            rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len())
+        }
+        else {
+            first.len_utf8()
+        };
+
+        lexemes.push(&rest[..length]);
+        rest = &rest[length..];
+    }
+
+    lexemes
+}
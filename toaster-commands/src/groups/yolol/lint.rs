@@ -0,0 +1,70 @@
+use std::fmt;
+
+use super::diagnostics;
+
+// Limits imposed by a yolol chip in Starbase
+pub const MAX_LINE_LENGTH: usize = 70;
+pub const MAX_LINES: usize = 20;
+
+/// A single problem found on a line. Lines and columns are 1-indexed to match what the game shows.
+#[derive(Debug)]
+pub struct LintIssue
+{
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.column
+        {
+            Some(column) => write!(f, "line {}, col {}: {}", self.line, column, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+/// Checks every line against the chip limits and tries to parse each one on its own,
+/// so a broken line doesn't hide problems on the lines after it.
+pub fn lint(lines: &[String]) -> Vec<LintIssue>
+{
+    let mut issues = vec![];
+
+    for (index, line) in lines.iter().enumerate()
+    {
+        let line_number = index + 1;
+
+        if line_number > MAX_LINES
+        {
+            issues.push(LintIssue {
+                line: line_number,
+                column: None,
+                message: format!("past the end of a chip, which only fits {} lines", MAX_LINES),
+            });
+        }
+
+        let length = line.chars().count();
+        if length > MAX_LINE_LENGTH
+        {
+            issues.push(LintIssue {
+                line: line_number,
+                column: Some(MAX_LINE_LENGTH + 1),
+                message: format!("line is {} characters long, the limit is {}", length, MAX_LINE_LENGTH),
+            });
+        }
+
+        if let Some(diagnostic) = diagnostics::diagnose_line(line_number, line)
+        {
+            issues.push(LintIssue {
+                line: line_number,
+                column: Some(diagnostic.column),
+                message: format!("{} failure: {}", diagnostic.stage, diagnostic.message),
+            });
+        }
+    }
+
+    issues
+}
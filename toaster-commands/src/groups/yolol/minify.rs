@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use cylon_ast::{
+    CylonProg,
+    CylonStat,
+    CylonExpr,
+};
+
+use yoloxide::types::VecWindow;
+
+use super::lint::MAX_LINE_LENGTH;
+use super::syntax::{
+    KEYWORDS,
+    UnaryForm,
+    is_word_char,
+    strip_groups,
+    unary_form,
+};
+
+pub struct Minified
+{
+    pub lines: Vec<String>,
+    pub original_length: usize,
+    // Lowercase original name of each local to the name it was given
+    pub names: HashMap<String, String>,
+}
+
+impl Minified
+{
+    pub fn length(&self) -> usize
+    {
+        self.lines.iter().map(|line| line.chars().count()).sum()
+    }
+
+    /// 1-indexed numbers of the lines that still don't fit on a chip
+    pub fn long_lines(&self) -> Vec<usize>
+    {
+        self.lines.iter()
+            .enumerate()
+            .filter(|(_, line)| line.chars().count() > MAX_LINE_LENGTH)
+            .map(|(index, _)| index + 1)
+            .collect()
+    }
+}
+
+/// Re-emits a program with the least characters we can manage. Comments are dropped,
+/// locals get the shortest names available and only the parentheses that matter are kept.
+pub fn minify(prog: &CylonProg, original_length: usize) -> Result<Minified, String>
+{
+    let names = short_names(prog);
+
+    let lines: Vec<String> = prog.lines.iter()
+        .map(|line| {
+            let stats: Vec<String> = line.code.iter()
+                .map(|stat| stat_string(stat, &names))
+                .collect();
+
+            join_statements(&stats)
+        })
+        .collect();
+
+    // Make sure we didn't produce something the parser won't take back
+    for (index, line) in lines.iter().enumerate()
+    {
+        let tokens = yoloxide::tokenizer::tokenize(line.clone())
+            .map_err(|error| format!("Minifying produced code that doesn't tokenize on line {}! Error: ```{}```", index + 1, error))?;
+
+        let mut window = VecWindow::new(tokens, 0);
+        yoloxide::parser::parse_program(&mut window)
+            .map_err(|error| format!("Minifying produced code that doesn't parse on line {}! Error: ```{}```", index + 1, error))?;
+    }
+
+    Ok(Minified {
+        lines,
+        original_length,
+        names,
+    })
+}
+
+// Yolol is case insensitive, so names are compared lowercase
+fn normalize(name: &str) -> String
+{
+    name.to_lowercase()
+}
+
+fn is_global(name: &str) -> bool
+{
+    name.starts_with(':')
+}
+
+/// Maps each local to a new name, giving the shortest names to the most used variables
+fn short_names(prog: &CylonProg) -> HashMap<String, String>
+{
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for line in &prog.lines
+    {
+        for stat in &line.code
+        {
+            visit_stat_names(stat, &mut |name| {
+                if !is_global(name)
+                {
+                    *counts.entry(normalize(name)).or_insert(0) += 1;
+                }
+            });
+        }
+    }
+
+    let mut by_usage: Vec<(String, usize)> = counts.into_iter().collect();
+    // Sort by name as well so the output is stable between runs
+    by_usage.sort_by(|(name_a, count_a), (name_b, count_b)| count_b.cmp(count_a).then(name_a.cmp(name_b)));
+
+    let mut generated = (0..).map(generate_name).filter(|name| !KEYWORDS.contains(&name.as_str()));
+
+    by_usage.into_iter()
+        .map(|(name, _)| (name, generated.next().unwrap()))
+        .collect()
+}
+
+// 0 -> a, 25 -> z, 26 -> aa, ...
+fn generate_name(mut index: usize) -> String
+{
+    let mut name = vec![];
+
+    loop
+    {
+        name.push(b'a' + (index % 26) as u8);
+        if index < 26 { break }
+        index = index / 26 - 1;
+    }
+
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+fn visit_stat_names<F: FnMut(&str)>(stat: &CylonStat, visit: &mut F)
+{
+    match stat
+    {
+        CylonStat::Goto { expression } => visit_expr_names(expression, visit),
+        CylonStat::If { condition, body, else_body } => {
+            visit_expr_names(condition, visit);
+            body.iter().chain(else_body.iter()).for_each(|stat| visit_stat_names(stat, visit));
+        },
+        CylonStat::Assignment { identifier, value, .. } => {
+            visit(identifier);
+            visit_expr_names(value, visit);
+        },
+        CylonStat::Expression { expression } => visit_expr_names(expression, visit),
+    }
+}
+
+fn visit_expr_names<F: FnMut(&str)>(expr: &CylonExpr, visit: &mut F)
+{
+    match expr
+    {
+        CylonExpr::Group { group } => visit_expr_names(group, visit),
+        CylonExpr::BinaryOp { left, right, .. } => {
+            visit_expr_names(left, visit);
+            visit_expr_names(right, visit);
+        },
+        CylonExpr::UnaryOp { operand, .. } => visit_expr_names(operand, visit),
+        CylonExpr::Identifier { name } => visit(name),
+        CylonExpr::Number { .. } |
+        CylonExpr::String { .. } => (),
+    }
+}
+
+fn rename(name: &str, names: &HashMap<String, String>) -> String
+{
+    if is_global(name)
+    {
+        return name.to_owned()
+    }
+
+    names.get(&normalize(name)).cloned().unwrap_or_else(|| name.to_owned())
+}
+
+fn stat_string(stat: &CylonStat, names: &HashMap<String, String>) -> String
+{
+    match stat
+    {
+        CylonStat::Goto { expression } => {
+            join_tokens(&["goto".to_owned(), expr_string(expression, names)])
+        },
+        CylonStat::If { condition, body, else_body } => {
+            let mut parts = vec!["if".to_owned(), expr_string(condition, names), "then".to_owned()];
+            parts.extend(body.iter().map(|stat| stat_string(stat, names)));
+
+            if !else_body.is_empty()
+            {
+                parts.push("else".to_owned());
+                parts.extend(else_body.iter().map(|stat| stat_string(stat, names)));
+            }
+
+            parts.push("end".to_owned());
+            join_statements(&parts)
+        },
+        CylonStat::Assignment { identifier, operator, value } => {
+            join_tokens(&[rename(identifier, names), operator.clone(), expr_string(value, names)])
+        },
+        CylonStat::Expression { expression } => expr_string(expression, names),
+    }
+}
+
+/// Glues tokens together, only adding a space where leaving it out would merge them
+fn join_tokens(tokens: &[String]) -> String
+{
+    let mut output = String::new();
+
+    for token in tokens
+    {
+        if needs_space(&output, token)
+        {
+            output.push(' ');
+        }
+        output += token;
+    }
+
+    output
+}
+
+/// Like `join_tokens`, but also keeps a trailing `a++` or `a!` from gluing onto what comes next
+fn join_statements(stats: &[String]) -> String
+{
+    let mut output = String::new();
+
+    for stat in stats
+    {
+        let ends_in_operator = output.ends_with(&['+', '-', '!'][..]);
+        if !output.is_empty() && (ends_in_operator || needs_space(&output, stat))
+        {
+            output.push(' ');
+        }
+        output += stat;
+    }
+
+    output
+}
+
+fn needs_space(left: &str, right: &str) -> bool
+{
+    match (left.chars().last(), right.chars().next())
+    {
+        (Some(l), Some(r)) => {
+            (is_word_char(l) && is_word_char(r)) ||
+            // Stops things like `a- -b` from turning into a decrement
+            ((l == '-' || l == '+') && (r == '-' || r == '+'))
+        },
+        _ => false
+    }
+}
+
+// Higher binds tighter, following yoloxide's parser. Unusually, `and` binds looser than `or`, and
+// equality is looser than ordering. Every level is left associative apart from `^`.
+fn binary_precedence(operator: &str) -> u8
+{
+    match operator
+    {
+        "and" => 1,
+        "or" => 2,
+        "==" | "!=" => 3,
+        "<" | ">" | "<=" | ">=" => 4,
+        "+" | "-" => 6,
+        "*" | "/" | "%" => 7,
+        "^" => 9,
+        _ => 0,
+    }
+}
+
+fn is_atom(expr: &CylonExpr) -> bool
+{
+    matches!(strip_groups(expr), CylonExpr::Number { .. } | CylonExpr::String { .. } | CylonExpr::Identifier { .. })
+}
+
+fn minify_number(num: &str) -> String
+{
+    if num.contains('.')
+    {
+        let trimmed = num.trim_end_matches('0').trim_end_matches('.');
+        if trimmed.is_empty() { "0".to_owned() } else { trimmed.to_owned() }
+    }
+    else
+    {
+        num.to_owned()
+    }
+}
+
+/// Writes out an expression on its own, keeping every name as is
+pub fn expression_string(expr: &CylonExpr) -> String
+{
+    expr_string(expr, &HashMap::new())
+}
+
+fn expr_string(expr: &CylonExpr, names: &HashMap<String, String>) -> String
+{
+    match strip_groups(expr)
+    {
+        CylonExpr::Number { num } => minify_number(num),
+        CylonExpr::String { str } => format!("\"{}\"", str),
+        CylonExpr::Identifier { name } => rename(name, names),
+
+        CylonExpr::UnaryOp { operator, operand } => {
+            let operand_string = if is_atom(operand) {
+                expr_string(operand, names)
+            }
+            else {
+                format!("({})", expr_string(operand, names))
+            };
+
+            match unary_form(operator)
+            {
+                UnaryForm::Prefix(symbol) => join_tokens(&[symbol.to_owned(), operand_string]),
+                UnaryForm::Postfix(symbol) => format!("{}{}", operand_string, symbol),
+                UnaryForm::Keyword => join_tokens(&[operator.clone(), operand_string]),
+            }
+        },
+
+        CylonExpr::BinaryOp { operator, left, right } => {
+            let precedence = binary_precedence(operator);
+
+            let left_string = operand_string(left, precedence, true, names);
+            let right_string = operand_string(right, precedence, false, names);
+
+            join_tokens(&[left_string, operator.clone(), right_string])
+        },
+
+        CylonExpr::Group { .. } => unreachable!("Groups are stripped above"),
+    }
+}
+
+fn operand_string(operand: &CylonExpr, parent_precedence: u8, is_left: bool, names: &HashMap<String, String>) -> String
+{
+    let operand = strip_groups(operand);
+    let output = expr_string(operand, names);
+
+    let needs_parens = match operand
+    {
+        CylonExpr::BinaryOp { operator, .. } => {
+            let precedence = binary_precedence(operator);
+            // Everything is left associative except `^`, which we never leave to chance
+            precedence < parent_precedence ||
+            (precedence == parent_precedence && (!is_left || operator == "^"))
+        },
+        // yoloxide binds every unary op tighter than any binary one, including `not`, so these parentheses are
+        // only defensive. They keep the code meaning the same to parsers that let a unary op on the left take the
+        // rest of the expression, or that bind `not` looser than arithmetic and comparisons
+        CylonExpr::UnaryOp { operator, .. } => {
+            is_left ||
+            parent_precedence >= binary_precedence("^") ||
+            (operator == "not" && parent_precedence >= binary_precedence("=="))
+        },
+        _ => false,
+    };
+
+    if needs_parens { format!("({})", output) } else { output }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::parse_yolol;
+    use super::super::config::{
+        YololConfig,
+        YololInput,
+    };
+    use super::super::optimize::check_equivalent_sandboxed;
+
+    fn minified(code: &str) -> Vec<String>
+    {
+        let prog = parse_yolol(YololInput::Yolol(code.to_owned())).expect("Test code failed to parse!");
+        minify(&prog.into(), code.len()).expect("Minifying failed!").lines
+    }
+
+    #[test]
+    fn minified_code_runs_the_same()
+    {
+        let code = "total = 0 i = 1\ntotal += i i++\nif i <= 5 then goto 2 end :out = total";
+        let prog = parse_yolol(YololInput::Yolol(code.to_owned())).expect("Test code failed to parse!");
+        let original: Vec<String> = prog.0.iter().map(|line| line.to_string()).collect();
+
+        let minified = minify(&prog.into(), code.len()).expect("Minifying failed!");
+        assert_eq!(check_equivalent_sandboxed(&original, &minified.lines, &YololConfig::new(), &[], &minified.names), Ok(()));
+
+        // And the check does notice when something's off
+        let broken = vec![minified.lines[0].clone(), minified.lines[1].clone(), String::new()];
+        assert!(check_equivalent_sandboxed(&original, &broken, &YololConfig::new(), &[], &minified.names).is_err());
+    }
+
+    #[test]
+    fn most_used_locals_get_the_shortest_names()
+    {
+        assert_eq!(minified("total = 0 x = 5 total = total + x"), vec!["a=0 b=5 a=a+b"]);
+    }
+
+    #[test]
+    fn globals_keep_their_names()
+    {
+        assert_eq!(minified("count = count + 1\n:out = count // done"), vec!["a=a+1", ":out=a"]);
+    }
+
+    #[test]
+    fn only_needed_parentheses_are_kept()
+    {
+        assert_eq!(minified(":a = (:b + :c) * :d"), vec![":a=(:b+:c)*:d"]);
+        assert_eq!(minified(":a = :b + (:c * :d)"), vec![":a=:b+:c*:d"]);
+        assert_eq!(minified(":a = (2 ^ 3) ^ 2"), vec![":a=(2^3)^2"]);
+    }
+
+    #[test]
+    fn and_binds_looser_than_or()
+    {
+        assert_eq!(minified(":a = (:b and :c) or :d"), vec![":a=(:b and :c)or :d"]);
+        assert_eq!(minified(":a = :b and (:c or :d)"), vec![":a=:b and :c or :d"]);
+    }
+
+    #[test]
+    fn not_on_the_right_keeps_its_parentheses()
+    {
+        assert_eq!(minified(":a = 1 + (not :b)"), vec![":a=1+(not :b)"]);
+    }
+
+    #[test]
+    fn increments_keep_their_side()
+    {
+        assert_eq!(minified(":a++ :b = ++:c"), vec![":a++ :b=++:c"]);
+    }
+
+    #[test]
+    fn trailing_zeroes_are_trimmed()
+    {
+        assert_eq!(minified(":a = 1.500 :b = 2.000"), vec![":a=1.5 :b=2"]);
+    }
+}
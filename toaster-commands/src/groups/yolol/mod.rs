@@ -1,7 +1,18 @@
 use std::convert::TryInto;
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+use std::collections::{
+    BTreeMap,
+    btree_map::Entry,
+};
 
 use serenity::prelude::*;
-use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::model::channel::{
+    Message,
+    Reaction,
+    ReactionType,
+};
 
 use serenity::framework::standard::{
     CommandResult,
@@ -23,6 +34,11 @@ use yoloxide::{
 
 use cylon_ast::CylonRoot;
 
+use toaster_core::{
+    reaction_forwarding,
+    plugin_tasks,
+};
+
 use regex::Regex;
 use lazy_static::lazy_static;
 
@@ -36,62 +52,520 @@ use config::{
     OutputFlag
 };
 
+mod execution;
+use execution::StopReason;
+
+mod diagnostics;
+mod lint;
+mod minify;
+mod syntax;
+
+mod control_flow;
+use control_flow::{
+    ControlFlow,
+    GraphFormat,
+};
+
+mod variables;
+mod assertions;
+mod profile;
+mod sweep;
+mod verify;
+mod highlight;
+
+mod reply;
+mod debugger;
+mod optimize;
+mod explain;
+mod doc;
+
+mod snippets;
+use snippets::{
+    Scope,
+    Snippet,
+};
+
+mod saved_envs;
+use saved_envs::SavedEnvironment;
+
 group!({
     name: "yolol",
-    options: {},
-    commands: [yolol],
+    options: {
+        prefixes: ["yolol"],
+        default_command: yolol,
+    },
+    commands: [yolol, yolol_test, yolol_verify, yolol_save, yolol_load, yolol_list, yolol_delete, yolol_run, yolol_debug, yolol_env, yolol_explain, yolol_doc],
 });
 
 lazy_static! {
-    static ref CODE_MATCHER: Regex = Regex::new(r"\A(?s:\n*)```(?s:[a-z]*\n)?((?s).*)\n?```\z").expect("Code matching regex failed to compile!");
+    static ref CODE_MATCHER: Regex = Regex::new(r"```(?s:[a-z]*\n)?((?s).*?)\n?```").expect("Code matching regex failed to compile!");
 }
 
-fn extract_input(input: &str) -> Result<&str, &str>
+fn extract_inputs(input: &str) -> Result<Vec<&str>, &str>
 {
-    // The regex ensures the input was formatted into a code block and has a capture group for the text of the input
-    let captures = match CODE_MATCHER.captures(input)
+    // Each code block is treated as its own chip. There has to be at least one, and nothing but whitespace between them
+    let leftover = CODE_MATCHER.replace_all(input, "");
+    if !CODE_MATCHER.is_match(input) || !leftover.trim().is_empty()
     {
-        Some(captures) => captures,
-        None => {
-            return Err("Your supplied code isn't properly put into a code block. Be sure to surround it with triple backticks!")
+        return Err("Your supplied code isn't properly put into a code block. Be sure to surround it with triple backticks!")
+    }
+
+    // Capture 0 is the whole block. The input capture is the only capture, meaning it's capture 1
+    CODE_MATCHER.captures_iter(input)
+        .map(|captures| match captures.get(1)
+        {
+            Some(capture) => Ok(capture.as_str()),
+            None => Err("Something is wrong with extracting input from code block! Someone might have broken the regex we use...")
+        })
+        .collect()
+}
+
+const MAX_ATTACHMENT_SIZE: u64 = 32 * 1024;
+
+// Pulls code out of any files attached to the message. Like code blocks, each file is its own chip.
+// Json files are always read as Cylon AST, everything else follows the input flag.
+fn extract_attachments(message: &Message, input_flag: InputFlag) -> Result<Vec<(InputFlag, String)>, String>
+{
+    let mut inputs = vec![];
+
+    for attachment in &message.attachments
+    {
+        let filename = attachment.filename.to_lowercase();
+
+        let input_flag = if filename.ends_with(".json") {
+            InputFlag::CylonAst
+        }
+        else if filename.ends_with(".yolol") || filename.ends_with(".txt") {
+            input_flag
+        }
+        else {
+            return Err(format!("I don't know what to do with `{}`! Attach a `.yolol`, `.txt` or `.json` file instead", attachment.filename))
+        };
+
+        if attachment.size > MAX_ATTACHMENT_SIZE
+        {
+            return Err(format!("`{}` is too big! Attachments can be at most {} KiB", attachment.filename, MAX_ATTACHMENT_SIZE / 1024))
+        }
+
+        let bytes = attachment.download()
+            .map_err(|error| format!("Failed to download `{}`! Error: ```{}```", attachment.filename, error))?;
+
+        let text = String::from_utf8(bytes)
+            .map_err(|_| format!("`{}` isn't valid UTF-8 text!", attachment.filename))?;
+
+        inputs.push((input_flag, text.replace("\r\n", "\n")));
+    }
+
+    Ok(inputs)
+}
+
+// Splits the input into the lines of code a chip would run.
+// Cylon ASTs are converted into a program first, then reconstructed into code line by line.
+fn input_lines(input: YololInput) -> Result<Vec<String>, String>
+{
+    match input
+    {
+        YololInput::Yolol(code) => Ok(code.lines().map(String::from).collect()),
+
+        cylon_ast => {
+            let prog = parse_yolol(cylon_ast)?;
+            Ok(format!("{}", prog).lines().map(String::from).collect())
+        }
+    }
+}
+
+fn output_execution(inputs: Vec<YololInput>, config: &YololConfig, envs: &mut Vec<Environment>) -> Result<StopReason, String>
+{
+    let chips = inputs.into_iter()
+        .map(input_lines)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The worker takes ownership while it runs, then hands the environments back
+    let (stop_reason, finished_envs) = execution::run_network_sandboxed(chips, config.clone(), std::mem::take(envs))?;
+    *envs = finished_envs;
+
+    Ok(stop_reason)
+}
+
+fn output_sweep(inputs: Vec<YololInput>, config: &YololConfig, env_names: &[String]) -> Result<String, String>
+{
+    let chips = inputs.into_iter()
+        .map(input_lines)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let runs = sweep::run_sweep(chips, config, env_names)?;
+    Ok(sweep::render(&runs, &config.sweep_fields))
+}
+
+fn output_trace(input: YololInput, config: &YololConfig, env: &mut Environment) -> Result<(StopReason, String), String>
+{
+    let lines = input_lines(input)?;
+    let config = config.clone();
+    let mut worker_env = env.clone();
+
+    let (stop_reason, trace, finished_env) = execution::sandboxed(move || {
+        let mut trace = String::new();
+        let mut last_snapshot = execution::snapshot(&worker_env);
+
+        let stop_reason = execution::run(&lines, &config, &mut worker_env, |tick, line, env| {
+            let current_snapshot = execution::snapshot(env);
+
+            let changes: Vec<String> = current_snapshot.iter()
+                .filter(|&(name, value)| last_snapshot.get(name) != Some(value))
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+
+            trace += &format!("tick {:>5} | line {:>2} | {}\n", tick, line, changes.join(", "));
+            last_snapshot = current_snapshot;
+        })?;
+
+        Ok((stop_reason, trace, worker_env))
+    })?;
+
+    *env = finished_env;
+    Ok((stop_reason, trace))
+}
+
+fn output_profile(input: YololInput, config: &YololConfig, env: &mut Environment) -> Result<(StopReason, String), String>
+{
+    let lines = input_lines(input)?;
+    let config = config.clone();
+    let mut worker_env = env.clone();
+
+    let (stop_reason, profile, finished_env) = execution::sandboxed(move || {
+        let mut profile = profile::Profile::new(lines.len());
+
+        let stop_reason = execution::run(&lines, &config, &mut worker_env, |_, line, env| {
+            profile.record(line, !env.error.is_empty());
+        })?;
+
+        Ok((stop_reason, profile.render(&lines), worker_env))
+    })?;
+
+    *env = finished_env;
+    Ok((stop_reason, profile))
+}
+
+fn output_optimized(input: YololInput, config: &YololConfig) -> Result<String, String>
+{
+    let original = input_lines(input)?;
+    let prog = parse_yolol(YololInput::Yolol(original.join("\n")))?;
+
+    let report = optimize::optimize(prog.into(), &original, config)?;
+
+    let changed = !report.applied.is_empty();
+
+    let mut summary = report.applied;
+    summary.extend(report.rejected);
+
+    if changed
+    {
+        let before: usize = original.iter().map(|line| line.chars().count()).sum();
+        let after: usize = report.lines.iter().map(|line| line.chars().count()).sum();
+        summary.push(format!("Checked against the original over {} ticks. {} characters before, {} after", config.tick_limit, before, after));
+    }
+    else if summary.is_empty()
+    {
+        summary.push("Found nothing to optimize!".to_owned());
+    }
+    else
+    {
+        summary.push("Nothing made it through, so the code is unchanged.".to_owned());
+    }
+
+    Ok(format!("{}\n```\n{}\n```", summary.join("\n"), report.lines.join("\n")))
+}
+
+fn output_lint(input: YololInput) -> Result<String, String>
+{
+    let lines = input_lines(input)?;
+    let issues = lint::lint(&lines);
+
+    if issues.is_empty()
+    {
+        return Ok(format!("No problems found! All {} lines fit on a chip.", lines.len()))
+    }
+
+    let report: Vec<String> = issues.iter()
+        .map(|issue| issue.to_string())
+        .collect();
+
+    Ok(format!("Found {} problems:\n{}", issues.len(), report.join("\n")))
+}
+
+fn output_minified(input: YololInput, config: &YololConfig) -> Result<String, String>
+{
+    let source_length = match &input
+    {
+        YololInput::Yolol(code) => Some(code.lines().map(|line| line.chars().count()).sum()),
+        YololInput::CylonAst(_) => None
+    };
+
+    let prog = parse_yolol(input)?;
+    // Cylon ASTs have no source text, so the best we can compare against is the reconstructed code
+    let original_length = source_length.unwrap_or_else(|| format!("{}", prog).lines().map(|line| line.chars().count()).sum());
+
+    let original: Vec<String> = prog.0.iter().map(|line| line.to_string()).collect();
+    let minified = minify::minify(&prog.into(), original_length)?;
+
+    optimize::check_equivalent_sandboxed(&original, &minified.lines, config, &[], &minified.names)
+        .map_err(|why| format!("Minifying changed what the code does, {}! The minifier got something wrong, so stick with the original.", why))?;
+
+    let fit_report = match minified.long_lines().as_slice()
+    {
+        [] => format!("Every line fits in {} characters!", lint::MAX_LINE_LENGTH),
+        long_lines => {
+            let long_lines: Vec<String> = long_lines.iter().map(|line| line.to_string()).collect();
+            format!("Still over {} characters on lines: {}", lint::MAX_LINE_LENGTH, long_lines.join(", "))
         }
     };
 
-    // Capture 0 is the whole thing. The input capture is the only capture, meaning it's capture 1
-    match captures.get(1)
+    Ok(format!("Minified from {} to {} characters. {} Checked against the original over {} ticks. ```\n{}\n```", minified.original_length, minified.length(), fit_report, config.tick_limit, minified.lines.join("\n")))
+}
+
+fn output_control_flow(input: YololInput, format: GraphFormat) -> Result<(String, String), String>
+{
+    let prog = parse_yolol(input)?;
+    let code_lines: Vec<String> = format!("{}", prog).lines().map(String::from).collect();
+
+    let flow = ControlFlow::analyze(&prog.into());
+    let graph = flow.render(format, &code_lines);
+
+    let list = |lines: Vec<usize>| lines.iter().map(|line| line.to_string()).collect::<Vec<String>>().join(", ");
+
+    let unreachable = flow.unreachable_lines();
+    let mut summary = vec![];
+
+    if unreachable.is_empty()
+    {
+        summary.push("Every line is reachable.".to_owned());
+    }
+    else
+    {
+        summary.push(format!("Unreachable lines: {}", list(unreachable)));
+    }
+
+    if !flow.computed_gotos.is_empty()
     {
-        Some(capture) => Ok(capture.as_str()),
-        None => Err("Something is wrong with extracting input from code block! Someone might have broken the regex we use...")
+        let lines = flow.computed_gotos.iter().cloned().collect();
+        summary.push(format!("Computed gotos that can't be resolved statically on lines: {}. Lines they jump to might not show up as reachable!", list(lines)));
     }
+
+    Ok((summary.join("\n"), graph))
 }
 
-fn output_execution(input: YololInput, env: &mut Environment) -> Result<(), String>
+fn output_variables(input: YololInput) -> Result<String, String>
 {
-    let tick_limit = 1000;
+    let prog = parse_yolol(input)?.into();
 
-    let code = match input
+    let flow = ControlFlow::analyze(&prog);
+    let usages = variables::analyze(&prog, &flow);
+
+    if usages.is_empty()
     {
-        YololInput::Yolol(code) => code,
-        YololInput::CylonAst(_) => {
-            return Err("Execution from Cylon AST not yet supported! Psst, try outputting yolol then using it as input ;)".to_owned())
+        return Ok("This program doesn't use any variables!".to_owned())
+    }
+
+    let list = |lines: &std::collections::BTreeSet<usize>| {
+        if lines.is_empty() { return "-".to_owned() }
+        lines.iter().map(|line| line.to_string()).collect::<Vec<String>>().join(",")
+    };
+
+    let name_width = usages.iter().map(|usage| usage.name.len()).max().unwrap_or(0).max(4);
+
+    let mut rows = vec![format!("{:<width$}  {:<6}  {:<12}  {:<12}  notes", "name", "kind", "read on", "written on", width = name_width)];
+
+    for usage in &usages
+    {
+        let mut notes = vec![];
+        if usage.read_before_write
+        {
+            notes.push(if usage.global { "read before written (device input?)" } else { "read before written" });
         }
+        if usage.written_never_read()
+        {
+            notes.push(if usage.global { "written, never read (device output?)" } else { "written, never read" });
+        }
+
+        rows.push(format!("{:<width$}  {:<6}  {:<12}  {:<12}  {}",
+            usage.name,
+            if usage.global { "global" } else { "local" },
+            list(&usage.reads),
+            list(&usage.writes),
+            notes.join(", "),
+            width = name_width));
+    }
+
+    Ok(rows.join("\n"))
+}
+
+// Runs the program once for every distinct tick count the assertions ask for, then checks each one
+// against the environment it ended up with
+fn output_tests(input: YololInput, test_block: Option<String>, config: &YololConfig) -> Result<String, String>
+{
+    // Rebuilding code from a Cylon AST drops its comments, so the assertions have to come from somewhere else
+    if test_block.is_none() && matches!(input, YololInput::CylonAst(_))
+    {
+        return Err("Assertions in comments get lost when converting a Cylon AST! Put one assertion per line in a second code block instead".to_owned())
+    }
+
+    let code = input_lines(input)?.join("\n");
+
+    let assertions = match test_block
+    {
+        Some(block) => assertions::from_test_block(&block)?,
+        None => assertions::from_comments(&code)?,
     };
 
-    let lines: Vec<String> = code.lines().map(String::from).collect();
-    let line_len: i64 = lines.len().try_into().unwrap();
+    if assertions.is_empty()
+    {
+        return Err("There's nothing to test! Add comments like `// expect :out==5 after 100` to your code, or put one assertion per line in a second code block".to_owned())
+    }
 
-    for _ in 0..tick_limit
+    if assertions.len() > assertions::MAX_CASES
     {
-        // This is a stupid line but I can't find a better way to do it for some reason...
-        let next_line = if env.next_line > line_len || env.next_line <= 0 { 1 } else { env.next_line };
-        env.next_line = next_line;
+        return Err(format!("Too many assertions! A test can have at most {}, but you gave {}", assertions::MAX_CASES, assertions.len()))
+    }
 
-        let next_line: usize = next_line.try_into().unwrap();
+    for assertion in &assertions
+    {
+        execution::validate_condition(&assertion.condition)?;
 
-        yoloxide::execute_line(env, lines[next_line - 1].clone());
+        if let Some(ticks) = assertion.ticks
+        {
+            if ticks == 0 || ticks > YololConfig::MAX_TICK_LIMIT
+            {
+                return Err(format!("The assertion on line {} asks for {} ticks, but it has to be from 1 to {}!", assertion.line, ticks, YololConfig::MAX_TICK_LIMIT))
+            }
+        }
     }
 
-    Ok(())
+    // Settling only cuts the run short once nothing can change anymore, so the end state is the same
+    let mut case_config = config.clone();
+    case_config.stop_condition = None;
+    case_config.stop_on_settle = true;
+
+    let mut runs: BTreeMap<usize, (StopReason, Environment)> = BTreeMap::new();
+    let mut report = vec![];
+    let mut passed = 0;
+
+    for assertion in &assertions
+    {
+        let ticks = assertion.ticks.unwrap_or(config.tick_limit);
+
+        let (stop_reason, env) = match runs.entry(ticks)
+        {
+            Entry::Occupied(run) => run.into_mut(),
+            Entry::Vacant(run) => {
+                case_config.tick_limit = ticks;
+
+                let mut envs = vec![Environment::new("Bot")];
+                let stop_reason = output_execution(vec![YololInput::Yolol(code.clone())], &case_config, &mut envs)?;
+                run.insert((stop_reason, envs.remove(0)))
+            },
+        };
+        let case = format!("line {}: {} after {} ticks", assertion.line, assertion.condition, ticks);
+
+        match *stop_reason
+        {
+            StopReason::TimedOut(_) | StopReason::MemoryLimit(_) => {
+                report.push(format!("ERROR {} ({})", case, stop_reason));
+            },
+            _ if execution::check_condition(env, &assertion.condition) => {
+                passed += 1;
+                report.push(format!("PASS  {}", case));
+            },
+            _ => {
+                let snapshot = execution::snapshot(env);
+                let values: Vec<String> = assertions::mentioned_variables(&assertion.condition).iter()
+                    .map(|name| {
+                        let value = snapshot.iter()
+                            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                            .map(|(_, value)| value.as_str())
+                            .unwrap_or("unset");
+                        format!("{} = {}", name, value)
+                    })
+                    .collect();
+
+                if values.is_empty()
+                {
+                    report.push(format!("FAIL  {}", case));
+                }
+                else
+                {
+                    report.push(format!("FAIL  {} (got {})", case, values.join(", ")));
+                }
+            },
+        }
+    }
+
+    Ok(format!("{} of {} assertion(s) passed\n{}", passed, assertions.len(), report.join("\n")))
+}
+
+// Sends the code through every representation we have and back into yolol text
+fn round_trip(code: String) -> Result<Vec<String>, String>
+{
+    let prog = parse_yolol(YololInput::Yolol(code))?;
+    let root = CylonRoot::new(prog.into());
+
+    // Cylon ASTs are shared as json, so that's part of the trip too
+    let json = serde_json::to_string(&root)
+        .map_err(|error| format!("Converting the Cylon AST to json failed with error: ```{}```", error))?;
+    let root: CylonRoot = serde_json::from_str(&json)
+        .map_err(|error| format!("Reading the Cylon AST back from json failed with error: ```{}```", error))?;
+
+    let prog: Program = root.program.try_into()
+        .map_err(|error| format!("Converting the Cylon AST back into a program failed with error: ```{}```", error))?;
+
+    Ok(format!("{}", prog).lines().map(String::from).collect())
+}
+
+// Runs the original and the round tripped code with the same settings, then compares where they ended up
+fn output_verify(input: YololInput, config: &YololConfig) -> Result<String, String>
+{
+    let original = input_lines(input)?;
+    let round_tripped = round_trip(original.join("\n"))?;
+
+    let mut output = vec![];
+
+    if original.len() != round_tripped.len()
+    {
+        output.push(format!("Line count changed from {} to {}, so every goto after the change is off!", original.len(), round_tripped.len()));
+    }
+
+    let changed = verify::changed_lines(&original, &round_tripped);
+    if !changed.is_empty()
+    {
+        output.push(format!("{} line(s) were reformatted:", changed.len()));
+        for (line, before, after) in changed
+        {
+            output.push(format!("  {:>2} - {}\n     + {}", line, before, after));
+        }
+    }
+
+    let (original_stop, original_envs) = execution::run_network_sandboxed(vec![original], config.clone(), vec![Environment::new("Original")])?;
+    let (round_trip_stop, round_trip_envs) = execution::run_network_sandboxed(vec![round_tripped], config.clone(), vec![Environment::new("Round trip")])?;
+
+    output.push(format!("Original {}", original_stop));
+    output.push(format!("Round trip {}", round_trip_stop));
+
+    let differences = verify::diff(&execution::snapshot(&original_envs[0]), &execution::snapshot(&round_trip_envs[0]));
+
+    if differences.is_empty()
+    {
+        output.push("Both ended up with the same variables. The round trip is equivalent!".to_owned());
+    }
+    else
+    {
+        output.push(format!("{} variable(s) ended up different:", differences.len()));
+        for difference in differences
+        {
+            let show = |value: Option<String>| value.unwrap_or_else(|| "unset".to_owned());
+            output.push(format!("  {}: original {}, round trip {}", difference.name, show(difference.original), show(difference.round_trip)));
+        }
+    }
+
+    Ok(output.join("\n"))
 }
 
 fn output_yolol(input: YololInput) -> Result<String, String>
@@ -142,18 +616,19 @@ fn output_tokens(input: YololInput) -> Result<String, String>
 
 fn parse_yolol(input: YololInput) -> Result<Program, String>
 {
-    if let YololInput::CylonAst(root) = input
+    let code = match input
     {
-        return Ok(root.program.try_into()?)
-    }
+        YololInput::Yolol(code) => code,
+        YololInput::CylonAst(root) => return Ok(root.program.try_into()?)
+    };
 
-    let tokens = tokenize_yolol(input)?;
+    let tokens = tokenize_yolol(YololInput::Yolol(code.clone()))?;
     let mut window = VecWindow::new(tokens, 0);
 
     match yoloxide::parser::parse_program(&mut window)
     {
         Ok(prog) => Ok(prog),
-        Err(error) => Err(format!("Parser failure: ```{}```", error)),
+        Err(error) => Err(located_failure(&code, "Parser", error)),
     }
 }
 
@@ -167,88 +642,296 @@ fn tokenize_yolol(input: YololInput) -> Result<Vec<Token>, String>
         }
     };
 
-    match yoloxide::tokenizer::tokenize(code)
+    match yoloxide::tokenizer::tokenize(code.clone())
     {
         Ok(tokens) => Ok(tokens),
-        Err(error) => Err(format!("Tokenizer failure: ```{}```", error)),
+        Err(error) => Err(located_failure(&code, "Tokenizer", error)),
+    }
+}
+
+// Checking line by line gives us positions for every broken line. If that somehow finds
+// nothing, the whole program error is still better than no error at all.
+fn located_failure(code: &str, stage: &str, error: impl std::fmt::Display) -> String
+{
+    let diagnostics = diagnostics::diagnose(code);
+
+    match diagnostics::report(&diagnostics)
+    {
+        Some(report) => report,
+        None => format!("{} failure: ```{}```", stage, error),
+    }
+}
+
+// Wraps the provided args in a new args struct, so we can control the delimiters used
+fn wrap_args(args: &Args) -> Args
+{
+    use serenity::framework::standard::Delimiter;
+    Args::new(args.message(), &[Delimiter::Single('\n'), Delimiter::Single(' ')])
+}
+
+// Anything after the flags is expected to be the input. It's fine to leave it out if the code is attached instead
+fn gather_inputs(message: &Message, config: &YololConfig, rest: &str) -> Result<Vec<(InputFlag, String)>, String>
+{
+    let mut raw_inputs = vec![];
+
+    if !rest.trim().is_empty() || message.attachments.is_empty()
+    {
+        let inputs = extract_inputs(rest)?;
+        raw_inputs.extend(inputs.into_iter().map(|input| (config.input, input.to_owned())));
+    }
+
+    raw_inputs.extend(extract_attachments(message, config.input)?);
+
+    // Quickly checks to make sure there's no backticks in the code, since they can break output formatting
+    if raw_inputs.iter().any(|(_, input)| input.contains('`'))
+    {
+        return Err("Your supplied code contains some backticks! No trying to break the output code blocks ;)".to_owned())
+    }
+
+    Ok(raw_inputs)
+}
+
+// For commands that work on a single chip. Parses the flags, then takes the one input that has to follow them.
+// `single_chip` is what to tell the user when they gave more than one.
+fn single_input(message: &Message, args: &mut Args, single_chip: &str) -> Result<(YololConfig, InputFlag, String), String>
+{
+    let config = YololConfig::parse_args(args)?;
+    let raw_inputs = gather_inputs(message, &config, args.rest())?;
+
+    if raw_inputs.len() > 1
+    {
+        return Err(single_chip.to_owned())
+    }
+
+    let (input_flag, input) = raw_inputs.into_iter().next().expect("Extracted inputs were empty!");
+    Ok((config, input_flag, input))
+}
+
+fn convert_input(input_flag: InputFlag, input: String) -> Result<YololInput, String>
+{
+    match input_flag
+    {
+        InputFlag::Yolol => Ok(YololInput::Yolol(input)),
+
+        InputFlag::CylonAst => match serde_json::from_str(&input) {
+            Ok(root) => Ok(YololInput::CylonAst(root)),
+            Err(error) => Err(format!("Converting Cylon AST json to internal representation failed with error: ```{}```", error))
+        },
     }
 }
 
 #[command]
 fn yolol(context: &mut Context, message: &Message, args: Args) -> CommandResult
 {
-    // Wrap the provided args in a new args struct, so we can control the delimiters used
-    let mut args = {
-        use serenity::framework::standard::{Args, Delimiter};
-        Args::new(args.message(), &[Delimiter::Single('\n'), Delimiter::Single(' ')])
-    };
+    let mut args = wrap_args(&args);
 
     // Parse arguments into a YololConfig
     let config = match YololConfig::parse_args(&mut args)
     {
         Ok(config) => config,
         Err(error) => {
-            message.channel_id.say(&context.http, error)?;
+            reply::say(context, message, error)?;
             return Ok(())
         }
     };
 
-    // Anything after the flags is expected to be the input
-    let input = match extract_input(args.rest())
+    let raw_inputs = match gather_inputs(message, &config, args.rest())
     {
-        Ok(input) => input,
+        Ok(inputs) => inputs,
         Err(error) => {
-            message.channel_id.say(&context.http, error)?;
+            reply::say(context, message, error)?;
             return Ok(())
         }
     };
 
-    // Quickly checks to make sure there's no backticks in the code, since they can break output formatting
-    if input.contains('`')
+    run_yolol(context, message, config, raw_inputs)
+}
+
+// Everything the yolol command does once it knows what to run and how. Anything else
+// that ends up with code to run, like a saved snippet, goes through here too.
+fn run_yolol(context: &mut Context, message: &Message, config: YololConfig, mut raw_inputs: Vec<(InputFlag, String)>) -> CommandResult
+{
+    // An initial environment comes in as the last input, so an exported json file can be attached after the code
+    let initial_envs = if config.environment_input
     {
-        message.channel_id.say(&context.http, "Your supplied code contains some backticks! No trying to break the output code blocks ;)")?;
-        return Ok(())
+        let (_, json) = raw_inputs.pop().expect("Extracted inputs were empty!");
+
+        let loaded = if raw_inputs.is_empty() {
+            Err("There's no code to run! With `--input=env_json` the environment goes last, after the code".to_owned())
+        }
+        else if config.environment.is_some() || !config.sweeps.is_empty() {
+            Err("Json environments can't be used with a stored environment or a sweep!".to_owned())
+        }
+        else {
+            saved_envs::from_json(&json, raw_inputs.len())
+        };
+
+        match loaded
+        {
+            Ok(saved) => Some(saved.iter().map(SavedEnvironment::restore).collect::<Vec<Environment>>()),
+            Err(error) => {
+                reply::say(context, message, error)?;
+                return Ok(())
+            }
+        }
     }
+    else
+    {
+        None
+    };
 
-    let input = match config.input
+    // Only execution knows how to deal with more than one chip, or where to put an initial environment
+    match config.output
     {
-        InputFlag::Yolol => YololInput::Yolol(input.to_owned()),
+        OutputFlag::Execution |
+        OutputFlag::EnvironmentJson => (),
+        _ if initial_envs.is_some() => {
+            reply::say(context, message, "Json environments only work when running code! Try again without an output flag, or with `--output=env_json`")?;
+            return Ok(())
+        },
+        _ if raw_inputs.len() > 1 => {
+            reply::say(context, message, "Only execution can handle multiple chips! Try again with a single code block")?;
+            return Ok(())
+        },
+        _ => ()
+    }
 
-        InputFlag::CylonAst => match serde_json::from_str(input) {
-            Ok(root) => YololInput::CylonAst(root),
+    let mut inputs = vec![];
+    for (input_flag, input) in raw_inputs
+    {
+        match convert_input(input_flag, input)
+        {
+            Ok(input) => inputs.push(input),
             Err(error) => {
-                message.channel_id.say(&context.http, format!("Converting Cylon AST json to internal representation failed with error: ```{}```", error))?;
+                reply::say(context, message, error)?;
                 return Ok(())
             }
-        },
-    };
+        }
+    }
+
+    // The regex can't match without at least one block, so there's always a first input
+    let mut inputs = inputs.into_iter();
+    let input = inputs.next().expect("Extracted inputs were empty!");
 
     println!("Output: {:?}, input: {:?}", config.input, config.output);
 
     match config.output
     {
-        OutputFlag::Execution => {
-            let mut env = Environment::new("Bot");
-            match output_execution(input, &mut env)
+        OutputFlag::Execution |
+        OutputFlag::EnvironmentJson => {
+            let inputs: Vec<YololInput> = std::iter::once(input).chain(inputs).collect();
+
+            let env_names: Vec<String> = match inputs.len()
             {
-                Ok(_) => (),
-                Err(e) => {
-                    message.channel_id.say(&context.http, e)?;
-                    return Ok(())
-                }
-            }
+                1 => vec!["Bot".to_owned()],
+                chip_count => (1..=chip_count).map(|chip| format!("Chip {}", chip)).collect()
+            };
 
-            let output = env.to_string();
-            if output.len() > 1900
+            if config.environment.is_some() && (inputs.len() > 1 || !config.sweeps.is_empty())
             {
-                use serenity::http::AttachmentType;
-                let attachment = vec![AttachmentType::Bytes((output.as_bytes(), "toaster_output.txt"))];
-                message.channel_id.send_files(&context.http, attachment, |m| m.content("The output was too long! Here's a file instead"))?;
+                reply::say(context, message, "Stored environments only work with a single chip and no sweeps!")?;
+                return Ok(())
             }
-            else
+
+            if !config.sweeps.is_empty()
+            {
+                let output = match output_sweep(inputs, &config, &env_names)
+                {
+                    Ok(o) => o,
+                    Err(e) => {
+                        reply::say(context, message, e)?;
+                        return Ok(())
+                    }
+                };
+
+                if output.len() > 1900
+                {
+                    reply::send_file(context, message, output.as_bytes(), "toaster_sweep.txt", "The sweep results were too long! Here's a file instead")?;
+                }
+                else
+                {
+                    let output = format!("Sweep results: ```{}```", output);
+                    reply::say(context, message, output)?;
+                }
+
+                return Ok(())
+            }
+
+            // Json environments go first. A stored environment picks up where the last run left off, or starts fresh under that name
+            let mut envs: Vec<Environment> = match (initial_envs, &config.environment)
+            {
+                (Some(envs), _) => envs,
+                (None, Some(name)) => match saved_envs::load(message.author.id.0, name)
+                {
+                    Ok(Some(saved)) => vec![saved.restore()],
+                    Ok(None) => vec![Environment::new(name)],
+                    Err(e) => {
+                        reply::say(context, message, e)?;
+                        return Ok(())
+                    }
+                },
+                (None, None) => env_names.iter().map(|name| Environment::new(name)).collect()
+            };
+
+            let stop_reason = match output_execution(inputs, &config, &mut envs)
+            {
+                Ok(stop_reason) => stop_reason,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(())
+                }
+            };
+
+            if let (Some(name), [env]) = (&config.environment, envs.as_slice())
+            {
+                if let Err(e) = saved_envs::save(message.author.id.0, name, &SavedEnvironment::capture(env))
+                {
+                    reply::say(context, message, e)?;
+                    return Ok(())
+                }
+            }
+
+            if let OutputFlag::EnvironmentJson = config.output
+            {
+                let saved: Vec<SavedEnvironment> = envs.iter().map(SavedEnvironment::capture).collect();
+                let json = match saved_envs::to_json(&saved)
+                {
+                    Ok(json) => json,
+                    Err(e) => {
+                        reply::say(context, message, e)?;
+                        return Ok(())
+                    }
+                };
+
+                if json.len() > 1900
+                {
+                    reply::send_file(context, message, json.as_bytes(), "toaster_environment.json", format!("Execution stopped: {}. The environment was too long! Here's a file instead", stop_reason))?;
+                }
+                else
+                {
+                    reply::say(context, message, format!("Execution stopped: {}. Output environment as json: ```json\n{}```", stop_reason, json))?;
+                }
+
+                return Ok(())
+            }
+
+            let output = match envs.as_slice()
+            {
+                [env] => env.to_string(),
+                envs => envs.iter()
+                    .enumerate()
+                    .map(|(index, env)| format!("Chip {}:\n{}", index + 1, env))
+                    .collect::<Vec<String>>()
+                    .join("\n\n")
+            };
+            if output.len() > 1900
             {
-                let output = format!("Output environment from execution: ```{}```", output);
-                message.channel_id.say(&context.http, output)?;
+                reply::send_file(context, message, output.as_bytes(), "toaster_output.txt", format!("Execution stopped: {}. The output was too long! Here's a file instead", stop_reason))?;
+            }
+            else
+            {
+                let output = format!("Execution stopped: {}. Output environment from execution: ```{}```", stop_reason, output);
+                reply::say(context, message, output)?;
             }
         },
         OutputFlag::Yolol => {
@@ -256,35 +939,51 @@ fn yolol(context: &mut Context, message: &Message, args: Args) -> CommandResult
             {
                 Ok(o) => o,
                 Err(e) => {
-                    message.channel_id.say(&context.http, e)?;
+                    reply::say(context, message, e)?;
                     return Ok(());
                 } 
             };
 
-            let output = format!("Reconstructed code: ```{}```", output);
-            message.channel_id.say(&context.http, output)?;
+            let output = format!("Reconstructed code: {}", highlight::code_block(&output, 1900));
+            reply::say(context, message, output)?;
+        },
+        OutputFlag::Optimized => {
+            let output = match output_optimized(input, &config)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_optimized.txt", "The optimized code was too long! Here's a file instead")?;
+            }
+            else
+            {
+                reply::say(context, message, format!("Optimized code: {}", output))?;
+            }
         },
         OutputFlag::CylonAst => {
             let output = match output_cylon_ast(input)
             {
                 Ok(o) => o,
                 Err(e) => {
-                    message.channel_id.say(&context.http, e)?;
+                    reply::say(context, message, e)?;
                     return Ok(());
                 }
             };
 
-            use serenity::http::AttachmentType;
-
             if output.len() > 2000
             {
-                let attachment = vec![AttachmentType::Bytes((output.as_bytes(), "cylon_ast.json"))];
-                message.channel_id.send_files(&context.http, attachment, |m| m.content("The code was too long! Here's a file instead"))?;
+                reply::send_file(context, message, output.as_bytes(), "cylon_ast.json", "The code was too long! Here's a file instead")?;
             }
             else
             {
                 let output = format!("Cylon AST of program:\n```json\n{}\n```", output);
-                message.channel_id.say(&context.http, output)?;
+                reply::say(context, message, output)?;
             }
 
         },
@@ -293,28 +992,787 @@ fn yolol(context: &mut Context, message: &Message, args: Args) -> CommandResult
             {
                 Ok(o) => o,
                 Err(e) => {
-                    message.channel_id.say(&context.http, e)?;
+                    reply::say(context, message, e)?;
                     return Ok(());
                 }
             };
 
             let output = format!("Parsed program: ```{:?}```", output);
-            message.channel_id.say(&context.http, output)?;
+            reply::say(context, message, output)?;
         },
         OutputFlag::Tokens => {
             let output = match output_tokens(input)
             {
                 Ok(o) => o,
                 Err(e) => {
-                    message.channel_id.say(&context.http, e)?;
+                    reply::say(context, message, e)?;
                     return Ok(());
                 }
             };
 
             let output = format!("Tokenized program: ```{:?}```", output);
-            message.channel_id.say(&context.http, output)?;
+            reply::say(context, message, output)?;
+        },
+        OutputFlag::ControlFlow(format) => {
+            let (summary, graph) = match output_control_flow(input, format)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            let (language, filename) = match format
+            {
+                GraphFormat::Dot => ("dot", "toaster_cfg.dot"),
+                GraphFormat::Mermaid => ("mermaid", "toaster_cfg.mmd"),
+            };
+
+            if summary.len() + graph.len() > 1900
+            {
+                reply::send_file(context, message, graph.as_bytes(), filename, format!("{}\nThe graph was too long! Here's a file instead", summary))?;
+            }
+            else
+            {
+                let output = format!("{}\n```{}\n{}\n```", summary, language, graph);
+                reply::say(context, message, output)?;
+            }
+        },
+        OutputFlag::Variables => {
+            let output = match output_variables(input)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_vars.txt", "The variable report was too long! Here's a file instead")?;
+            }
+            else
+            {
+                let output = format!("Variable report: ```\n{}\n```", output);
+                reply::say(context, message, output)?;
+            }
+        },
+        OutputFlag::Minified => {
+            let output = match output_minified(input, &config)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_minified.txt", "The minified code was too long! Here's a file instead")?;
+            }
+            else
+            {
+                reply::say(context, message, output)?;
+            }
+        },
+        OutputFlag::Lint => {
+            let output = match output_lint(input)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_lint.txt", "The lint report was too long! Here's a file instead")?;
+            }
+            else
+            {
+                let output = format!("Lint report: ```{}```", output);
+                reply::say(context, message, output)?;
+            }
+        },
+        OutputFlag::Trace => {
+            let mut env = Environment::new("Bot");
+            let (stop_reason, output) = match output_trace(input, &config, &mut env)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_trace.txt", format!("Execution stopped: {}. The trace was too long! Here's a file instead", stop_reason))?;
+            }
+            else
+            {
+                let output = format!("Execution stopped: {}. Trace of execution: ```{}```", stop_reason, output);
+                reply::say(context, message, output)?;
+            }
+        },
+        OutputFlag::Profile => {
+            let mut env = Environment::new("Bot");
+            let (stop_reason, output) = match output_profile(input, &config, &mut env)
+            {
+                Ok(o) => o,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(());
+                }
+            };
+
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), "toaster_profile.txt", format!("Execution stopped: {}. The profile was too long! Here's a file instead", stop_reason))?;
+            }
+            else
+            {
+                let output = format!("Execution stopped: {}. Line profile: ```{}```", stop_reason, output);
+                reply::say(context, message, output)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[command("test")]
+fn yolol_test(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let config = match YololConfig::parse_args(&mut args)
+    {
+        Ok(config) => config,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+
+    let raw_inputs = match gather_inputs(message, &config, args.rest())
+    {
+        Ok(inputs) => inputs,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+
+    // The first input is the program, and a second one holds the assertions
+    let mut raw_inputs = raw_inputs.into_iter();
+    let (input_flag, input) = raw_inputs.next().expect("Extracted inputs were empty!");
+    let test_block = raw_inputs.next().map(|(_, block)| block);
+
+    if raw_inputs.next().is_some()
+    {
+        reply::say(context, message, "Tests take one program and at most one block of assertions! Try again with fewer code blocks")?;
+        return Ok(())
+    }
+
+    let output = match convert_input(input_flag, input).and_then(|input| output_tests(input, test_block, &config))
+    {
+        Ok(o) => o,
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    if output.len() > 1900
+    {
+        reply::send_file(context, message, output.as_bytes(), "toaster_tests.txt", "The test report was too long! Here's a file instead")?;
+    }
+    else
+    {
+        let output = format!("Test results: ```{}```", output);
+        reply::say(context, message, output)?;
+    }
+
+    Ok(())
+}
+
+#[command("verify")]
+fn yolol_verify(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let (config, input_flag, input) = match single_input(message, &mut args, "Verifying works on one chip at a time! Try again with a single code block")
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
         }
+    };
+
+    let output = match convert_input(input_flag, input).and_then(|input| output_verify(input, &config))
+    {
+        Ok(o) => o,
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    if output.len() > 1900
+    {
+        reply::send_file(context, message, output.as_bytes(), "toaster_verify.txt", "The verification report was too long! Here's a file instead")?;
+    }
+    else
+    {
+        let output = format!("Round trip verification: ```{}```", output);
+        reply::say(context, message, output)?;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[command("explain")]
+fn yolol_explain(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let (_, input_flag, input) = match single_input(message, &mut args, "Explaining works on one chip at a time! Try again with a single code block")
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+
+    let output = match convert_input(input_flag, input).and_then(parse_yolol)
+    {
+        Ok(prog) => explain::explain(&prog.into()).join("\n"),
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    if output.len() > 1900
+    {
+        reply::send_file(context, message, output.as_bytes(), "toaster_explain.txt", "The explanation was too long! Here's a file instead")?;
+    }
+    else
+    {
+        reply::say(context, message, output)?;
+    }
+
+    Ok(())
+}
+
+// The date part of an RFC 3339 timestamp
+fn snippet_date(snippet: &Snippet) -> &str
+{
+    snippet.saved_at.get(..10).unwrap_or(&snippet.saved_at)
+}
+
+#[command("save")]
+fn yolol_save(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let name = match args.current()
+    {
+        Some(name) => name.to_owned(),
+        None => {
+            reply::say(context, message, "Give the snippet a name! Like `t>yolol save door_control` followed by the code")?;
+            return Ok(())
+        }
+    };
+    args.advance();
+
+    // Snippets are personal unless they're explicitly shared with the server
+    let scope = match args.current()
+    {
+        Some("--guild") => match message.guild_id
+        {
+            Some(guild_id) => {
+                args.advance();
+                Scope::Guild(guild_id.0)
+            },
+            None => {
+                reply::say(context, message, "Server snippets can only be saved from inside a server!")?;
+                return Ok(())
+            }
+        },
+        _ => Scope::User(message.author.id.0),
+    };
+
+    let (_, input_flag, input) = match single_input(message, &mut args, "A snippet holds a single chip! Try again with a single code block")
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+
+    // Snippets are always kept as yolol, so Cylon ASTs get converted on the way in
+    let code = match convert_input(input_flag, input).and_then(input_lines)
+    {
+        Ok(lines) => lines.join("\n"),
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    // Only the author gets to overwrite a shared snippet
+    if let Scope::Guild(_) = scope
+    {
+        match snippets::load(scope, &name)
+        {
+            Ok(Some(existing)) if existing.author_id != message.author.id.0 => {
+                reply::say(context, message, format!("`{}` is already a server snippet saved by {}, so you can't replace it!", existing.name, existing.author))?;
+                return Ok(())
+            },
+            Ok(_) => (),
+            Err(e) => {
+                reply::say(context, message, e)?;
+                return Ok(())
+            }
+        }
+    }
+
+    let snippet = Snippet {
+        name,
+        code,
+        author_id: message.author.id.0,
+        author: message.author.name.clone(),
+        saved_at: message.timestamp.to_rfc3339(),
+    };
+
+    match snippets::save(scope, &snippet)
+    {
+        Ok(true) => reply::say(context, message, format!("Updated {} snippet `{}`!", scope, snippet.name))?,
+        Ok(false) => reply::say(context, message, format!("Saved {} snippet `{}`!", scope, snippet.name))?,
+        Err(e) => reply::say(context, message, e)?,
+    };
+
+    Ok(())
+}
+
+#[command("load")]
+fn yolol_load(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let name = match args.current()
+    {
+        Some(name) => name,
+        None => {
+            reply::say(context, message, "Which snippet? Try `t>yolol list` to see them all")?;
+            return Ok(())
+        }
+    };
+
+    let (scope, snippet) = match snippets::find(message.author.id.0, message.guild_id.map(|guild_id| guild_id.0), name)
+    {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            reply::say(context, message, format!("There's no snippet called `{}`! Try `t>yolol list` to see them all", name))?;
+            return Ok(())
+        },
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    let header = format!("`{}`, a {} snippet saved by {} on {}:", snippet.name, scope, snippet.author, snippet_date(&snippet));
+
+    if header.len() + snippet.code.len() > 1900
+    {
+        let filename = format!("{}.yolol", snippet.name);
+        reply::send_file(context, message, snippet.code.as_bytes(), &filename, format!("{}\nThe code was too long! Here's a file instead", header))?;
+    }
+    else
+    {
+        let output = format!("{} {}", header, highlight::code_block(&snippet.code, 1900 - header.len()));
+        reply::say(context, message, output)?;
+    }
+
+    Ok(())
+}
+
+#[command("list")]
+fn yolol_list(context: &mut Context, message: &Message) -> CommandResult
+{
+    let scopes = std::iter::once(Scope::User(message.author.id.0)).chain(message.guild_id.map(|guild_id| Scope::Guild(guild_id.0)));
+
+    let mut output = vec![];
+
+    for scope in scopes
+    {
+        let snippets = match snippets::list(scope)
+        {
+            Ok(snippets) => snippets,
+            Err(e) => {
+                reply::say(context, message, e)?;
+                return Ok(())
+            }
+        };
+
+        output.push(format!("{} snippets ({}/{}):", if let Scope::User(_) = scope { "Personal" } else { "Server" }, snippets.len(), snippets::MAX_SNIPPETS));
+
+        if snippets.is_empty()
+        {
+            output.push("  none yet!".to_owned());
+        }
+
+        for snippet in snippets
+        {
+            output.push(format!("  {} - {} line(s), by {} on {}", snippet.name, snippet.code.lines().count(), snippet.author, snippet_date(&snippet)));
+        }
+    }
+
+    let output = output.join("\n");
+
+    if output.len() > 1900
+    {
+        reply::send_file(context, message, output.as_bytes(), "toaster_snippets.txt", "There's too many snippets to list! Here's a file instead")?;
+    }
+    else
+    {
+        reply::say(context, message, format!("```{}```", output))?;
+    }
+
+    Ok(())
+}
+
+#[command("delete")]
+fn yolol_delete(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let name = match args.current()
+    {
+        Some(name) => name,
+        None => {
+            reply::say(context, message, "Which snippet? Try `t>yolol list` to see them all")?;
+            return Ok(())
+        }
+    };
+
+    let (scope, snippet) = match snippets::find(message.author.id.0, message.guild_id.map(|guild_id| guild_id.0), name)
+    {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            reply::say(context, message, format!("There's no snippet called `{}`!", name))?;
+            return Ok(())
+        },
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    if snippet.author_id != message.author.id.0
+    {
+        reply::say(context, message, format!("`{}` was saved by {}, so only they can delete it!", snippet.name, snippet.author))?;
+        return Ok(())
+    }
+
+    match snippets::delete(scope, &snippet.name)
+    {
+        Ok(_) => reply::say(context, message, format!("Deleted {} snippet `{}`!", scope, snippet.name))?,
+        Err(e) => reply::say(context, message, e)?,
+    };
+
+    Ok(())
+}
+
+#[command("run")]
+fn yolol_run(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let name = match args.current()
+    {
+        Some(name) => name.to_owned(),
+        None => {
+            reply::say(context, message, "Which snippet? Try `t>yolol list` to see them all")?;
+            return Ok(())
+        }
+    };
+    args.advance();
+
+    let (_, snippet) = match snippets::find(message.author.id.0, message.guild_id.map(|guild_id| guild_id.0), &name)
+    {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            reply::say(context, message, format!("There's no snippet called `{}`! Try `t>yolol list` to see them all", name))?;
+            return Ok(())
+        },
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    // Any flags after the name work the same as they do for the yolol command
+    let config = match YololConfig::parse_args(&mut args)
+    {
+        Ok(config) => config,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+
+    run_yolol(context, message, config, vec![(InputFlag::Yolol, snippet.code)])
+}
+
+#[command("debug")]
+fn yolol_debug(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let (config, input_flag, input) = match single_input(message, &mut args, "The debugger runs one chip at a time! Try again with a single code block")
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            reply::say(context, message, error)?;
+            return Ok(())
+        }
+    };
+    let session = match convert_input(input_flag, input).and_then(input_lines).and_then(|lines| debugger::Session::new(lines, config))
+    {
+        Ok(session) => session,
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    let owner = message.author.id;
+    if let Err(e) = debugger::claim(owner.0)
+    {
+        reply::say(context, message, e)?;
+        return Ok(())
+    }
+
+    // The session message isn't a tracked reply, since re-running on edit would start a second session
+    let buttons = debugger::BUTTONS.iter().map(|(emoji, _)| *emoji);
+    let session_message = match message.channel_id.send_message(&context.http, |m| m.content(session.render(None)).reactions(buttons))
+    {
+        Ok(session_message) => session_message,
+        Err(error) => {
+            debugger::release(owner.0);
+            return Err(error.into())
+        }
+    };
+
+    let receiver = reaction_forwarding::subscribe(session_message.id);
+    let session_context = context.clone();
+
+    // Started through core, so this library stays loaded while the session runs even if the group is reloaded
+    let spawned = plugin_tasks::spawn(YOLOL_GROUP.name, "yolol-debugger".to_owned(), Box::new(move || {
+        run_debug_session(session_context, session_message, owner, session, receiver)
+    }));
+
+    if let Err(error) = spawned
+    {
+        debugger::release(owner.0);
+        reply::say(context, message, format!("Couldn't start the debugger! Error: ```{}```", error))?;
+    }
+
+    Ok(())
+}
+
+// Drives a debug session from its owner's reactions until they stop it or it times out
+fn run_debug_session(context: Context, mut session_message: Message, owner: UserId, mut session: debugger::Session, receiver: Receiver<Reaction>)
+{
+    let started = Instant::now();
+
+    let ending = loop
+    {
+        if started.elapsed() > debugger::MAX_SESSION_LENGTH
+        {
+            break format!("hit the {} minute limit", debugger::MAX_SESSION_LENGTH.as_secs() / 60)
+        }
+
+        let reaction = match receiver.recv_timeout(debugger::IDLE_TIMEOUT)
+        {
+            Ok(reaction) => reaction,
+            Err(_) => break format!("nothing was pressed for {} seconds", debugger::IDLE_TIMEOUT.as_secs())
+        };
+
+        // Only the owner gets to drive. That also skips the buttons the bot adds itself
+        if reaction.user_id != owner
+        {
+            continue
+        }
+
+        let action = match &reaction.emoji
+        {
+            ReactionType::Unicode(emoji) => debugger::Action::from_emoji(emoji),
+            _ => None
+        };
+
+        // Taking the reaction back off lets the same button be pressed again. It's fine if we aren't allowed to
+        let _ = reaction.delete(&context);
+
+        match action
+        {
+            Some(debugger::Action::Stop) => break "stopped by its owner".to_owned(),
+            Some(action) => session.apply(action),
+            None => continue
+        }
+
+        let _ = session_message.edit(&context, |m| m.content(session.render(None)));
+    };
+
+    reaction_forwarding::unsubscribe(session_message.id);
+    debugger::release(owner.0);
+
+    let _ = session_message.edit(&context, |m| m.content(session.render(Some(&ending))));
+}
+
+#[command("env")]
+fn yolol_env(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let mut args = wrap_args(&args);
+
+    let action = args.current().map(str::to_lowercase);
+    let name = args.advance().current().map(str::to_owned);
+
+    let (action, name) = match (action, name)
+    {
+        (Some(action), Some(name)) => (action, name),
+        _ => {
+            reply::say(context, message, "Usage: `t>yolol env show|reset|export <name>`. Runs use a stored environment with `--env=<name>`, and exports can be run with `--input=env_json`")?;
+            return Ok(())
+        }
+    };
+
+    let owner = message.author.id.0;
+
+    if action == "reset"
+    {
+        match saved_envs::reset(owner, &name)
+        {
+            Ok(true) => reply::say(context, message, format!("Reset environment `{}`! The next run starts fresh", name))?,
+            Ok(false) => reply::say(context, message, format!("There's no environment called `{}`!", name))?,
+            Err(e) => reply::say(context, message, e)?,
+        };
+
+        return Ok(())
+    }
+
+    let saved = match saved_envs::load(owner, &name)
+    {
+        Ok(Some(saved)) => saved,
+        Ok(None) => {
+            reply::say(context, message, format!("There's no environment called `{}`! Run some code with `--env={}` to make it", name, name))?;
+            return Ok(())
+        },
+        Err(e) => {
+            reply::say(context, message, e)?;
+            return Ok(())
+        }
+    };
+
+    match action.as_str()
+    {
+        "show" => {
+            let output = saved.restore().to_string();
+            if output.len() > 1900
+            {
+                reply::send_file(context, message, output.as_bytes(), &format!("{}.txt", name), format!("Environment `{}` is too big to show! Here's a file instead", name))?;
+            }
+            else
+            {
+                reply::say(context, message, format!("Environment `{}`, next on line {}: ```{}```", name, saved.next_line, output))?;
+            }
+        },
+        "export" => {
+            let json = match saved_envs::to_json(std::slice::from_ref(&saved))
+            {
+                Ok(json) => json,
+                Err(e) => {
+                    reply::say(context, message, e)?;
+                    return Ok(())
+                }
+            };
+
+            reply::send_file(context, message, json.as_bytes(), &format!("{}.json", name), format!("Here's environment `{}`", name))?;
+        },
+        _ => {
+            reply::say(context, message, format!("I don't know how to `{}` an environment! Try `show`, `reset` or `export`", action))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command("doc")]
+fn yolol_doc(context: &mut Context, message: &Message, args: Args) -> CommandResult
+{
+    let query = args.rest().trim();
+
+    if query.is_empty()
+    {
+        reply::say(context, message, format!("Usage: `t>yolol doc <topic>`. Topics: {}", doc::topics()))?;
+        return Ok(())
+    }
+
+    let matches = doc::search(query);
+
+    let entry = match matches.first()
+    {
+        Some(entry) => entry,
+        None => {
+            reply::say(context, message, format!("Couldn't find anything about `{}`! Topics: {}", query, doc::topics()))?;
+            return Ok(())
+        }
+    };
+
+    let mut output = format!("**{}**\n{}", entry.topic, entry.text);
+
+    // Examples go through the same execution as everything else, so what they show is what the bot really does
+    if let Some(example) = entry.example
+    {
+        let mut config = YololConfig::new();
+        config.tick_limit = doc::example_ticks(example);
+
+        let mut envs = vec![Environment::new("Bot")];
+        let result = match output_execution(vec![YololInput::Yolol(example.to_owned())], &config, &mut envs)
+        {
+            Ok(_) => envs[0].to_string(),
+            Err(e) => e,
+        };
+
+        output += &format!("\nExample: {}\nAfter running it once through: ```{}```", highlight::code_block(example, 1900), result);
+    }
+
+    let related: Vec<String> = matches.iter()
+        .skip(1)
+        .take(3)
+        .map(|entry| format!("`{}`", entry.topic))
+        .collect();
+
+    if !related.is_empty()
+    {
+        output += &format!("\nSee also: {}", related.join(", "));
+    }
+
+    if output.len() > 1900
+    {
+        reply::send_file(context, message, output.as_bytes(), "toaster_doc.txt", format!("The entry for `{}` was too long! Here's a file instead", entry.topic))?;
+    }
+    else
+    {
+        reply::say(context, message, output)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,437 @@
+use std::convert::TryInto;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use cylon_ast::{
+    CylonProg,
+    CylonStat,
+    CylonExpr,
+};
+
+use yoloxide::{
+    environment::Environment,
+    types::{
+        VecWindow,
+        ast::{
+            program::Program,
+            value::LiteralValue,
+        },
+    },
+};
+
+use super::config::YololConfig;
+use super::control_flow::{
+    self,
+    ControlFlow,
+};
+use super::execution::{
+    self,
+    TIME_LIMIT,
+    MAX_TOTAL_STRING_LENGTH,
+};
+use super::minify;
+use super::variables;
+
+// Name the folded value is stored under while yoloxide works it out for us
+const SCRATCH_VARIABLE: &str = "folded";
+
+/// What came out of optimizing, along with which passes made it in
+pub struct Report
+{
+    pub lines: Vec<String>,
+    pub applied: Vec<String>,
+    pub rejected: Vec<String>,
+}
+
+// A pass changes the program in place and says what it did, or returns `None` if it found nothing to do.
+// It also gives back any locals it made unobservable, which the equivalence check should skip.
+type Pass = fn(&mut CylonProg) -> Option<(String, Vec<String>)>;
+
+const PASSES: &[(&str, Pass)] = &[
+    ("Constant folding", fold_constants),
+    ("Dead store elimination", remove_dead_stores),
+    ("Unreachable code removal", remove_after_goto),
+];
+
+/// Runs each pass in turn. Every pass is checked by running the result side by side with the original
+/// code for the whole tick budget, and any pass that changes behavior is thrown out.
+pub fn optimize(prog: CylonProg, original: &[String], config: &YololConfig) -> Result<Report, String>
+{
+    let mut current = prog;
+    let mut ignored = vec![];
+
+    let mut report = Report {
+        lines: original.to_vec(),
+        applied: vec![],
+        rejected: vec![],
+    };
+
+    for (name, pass) in PASSES
+    {
+        let mut candidate = clone_program(&current);
+
+        let (summary, removed) = match pass(&mut candidate)
+        {
+            Some(change) => change,
+            None => continue,
+        };
+
+        let mut candidate_ignored = ignored.clone();
+        candidate_ignored.extend(removed);
+
+        let lines = match program_lines(&candidate, original.len())
+        {
+            Ok(lines) => lines,
+            Err(why) => {
+                report.rejected.push(format!("{} was thrown out, {}", name, why));
+                continue
+            }
+        };
+
+        match check_equivalent_sandboxed(original, &lines, config, &candidate_ignored, &HashMap::new())
+        {
+            Ok(()) => {
+                current = candidate;
+                ignored = candidate_ignored;
+                report.lines = lines;
+                report.applied.push(format!("{}: {}", name, summary));
+            },
+            Err(why) => report.rejected.push(format!("{} was thrown out, {}", name, why)),
+        }
+    }
+
+    Ok(report)
+}
+
+// Cylon ASTs don't implement Clone, but they do serialize
+fn clone_program(prog: &CylonProg) -> CylonProg
+{
+    serde_json::to_value(prog)
+        .and_then(serde_json::from_value)
+        .expect("Cylon AST failed to round trip through json!")
+}
+
+// Goto targets depend on line numbers, so the output always has as many lines as the original.
+// Each line is parsed again too, since a pass could leave behind something like an empty if.
+fn program_lines(prog: &CylonProg, line_count: usize) -> Result<Vec<String>, String>
+{
+    let program: Program = clone_program(prog).try_into()
+        .map_err(|error| format!("it produced a Cylon AST that doesn't convert back into a program: {}", error))?;
+
+    let mut lines: Vec<String> = format!("{}", program).lines().map(String::from).collect();
+
+    if lines.len() > line_count
+    {
+        return Err("it somehow added lines to the program".to_owned())
+    }
+
+    lines.resize(line_count, String::new());
+
+    for (index, line) in lines.iter().enumerate()
+    {
+        let parses = match yoloxide::tokenizer::tokenize(line.clone())
+        {
+            Ok(tokens) => yoloxide::parser::parse_program(&mut VecWindow::new(tokens, 0)).is_ok(),
+            Err(_) => false,
+        };
+
+        if !parses
+        {
+            return Err(format!("it produced code that doesn't parse on line {}", index + 1))
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Same as `check_equivalent`, but on a worker thread so a slow check can't hold up the command
+pub fn check_equivalent_sandboxed(original: &[String], optimized: &[String], config: &YololConfig, ignored: &[String], renamed: &HashMap<String, String>) -> Result<(), String>
+{
+    let original = original.to_vec();
+    let optimized = optimized.to_vec();
+    let config = config.clone();
+    let ignored = ignored.to_vec();
+    let renamed = renamed.clone();
+
+    execution::sandboxed(move || Ok(check_equivalent(&original, &optimized, &config, &ignored, &renamed)))
+        .unwrap_or_else(|why| Err(format!("checking it failed: {}", why)))
+}
+
+/// Runs both versions in lockstep, checking after every tick that they're on the same line with the same
+/// variables. Locals in `ignored` were optimized out on purpose, so they're allowed to differ.
+/// `renamed` maps lowercase local names in the original to what they're called in the optimized code.
+fn check_equivalent(original: &[String], optimized: &[String], config: &YololConfig, ignored: &[String], renamed: &HashMap<String, String>) -> Result<(), String>
+{
+    let mut original_env = Environment::new("Original");
+    let mut optimized_env = Environment::new("Optimized");
+
+    let renamed_values: Vec<(String, String)> = config.initial_values.iter()
+        .map(|(name, value)| (renamed.get(&name.to_lowercase()).cloned().unwrap_or_else(|| name.clone()), value.clone()))
+        .collect();
+
+    execution::seed_environment(&mut original_env, &config.initial_values);
+    execution::seed_environment(&mut optimized_env, &renamed_values);
+
+    // Optimized names are turned back into the original ones, so the snapshots line up
+    let original_names: HashMap<&str, &str> = renamed.iter().map(|(original, new)| (new.as_str(), original.as_str())).collect();
+
+    let is_ignored = |name: &str| ignored.iter().any(|ignored| ignored.eq_ignore_ascii_case(name));
+    let deadline = Instant::now() + TIME_LIMIT;
+
+    for tick in 1..=config.tick_limit
+    {
+        execution::step(original, &mut original_env);
+        execution::step(optimized, &mut optimized_env);
+
+        let original_next = execution::next_line(original, &original_env);
+        let optimized_next = execution::next_line(optimized, &optimized_env);
+
+        if original_next != optimized_next
+        {
+            return Err(format!("after tick {} the original goes to line {} but the optimized code goes to line {}", tick, original_next, optimized_next))
+        }
+
+        let original_snapshot = execution::snapshot(&original_env);
+        let optimized_snapshot: execution::Snapshot = execution::snapshot(&optimized_env).into_iter()
+            .map(|(name, value)| (original_names.get(name.as_str()).map(|&name| name.to_owned()).unwrap_or(name), value))
+            .collect();
+
+        let names = original_snapshot.keys().chain(optimized_snapshot.keys()).filter(|name| !is_ignored(name));
+        for name in names
+        {
+            let original_value = original_snapshot.get(name).map(String::as_str).unwrap_or("unset");
+            let optimized_value = optimized_snapshot.get(name).map(String::as_str).unwrap_or("unset");
+
+            if original_value != optimized_value
+            {
+                return Err(format!("after tick {} `{}` is {} in the original but {} in the optimized code", tick, name, original_value, optimized_value))
+            }
+        }
+
+        if execution::string_usage(&original_env) > MAX_TOTAL_STRING_LENGTH
+        {
+            return Err(format!("strings grew past {} characters before it could be checked", MAX_TOTAL_STRING_LENGTH))
+        }
+
+        if Instant::now() > deadline
+        {
+            return Err(format!("checking it took longer than {} seconds", TIME_LIMIT.as_secs()))
+        }
+    }
+
+    Ok(())
+}
+
+fn fold_constants(prog: &mut CylonProg) -> Option<(String, Vec<String>)>
+{
+    let mut folded = 0;
+
+    for line in &mut prog.lines
+    {
+        fold_stats(&mut line.code, &mut folded);
+    }
+
+    if folded == 0 { None } else { Some((format!("folded {} constant expression(s)", folded), vec![])) }
+}
+
+fn fold_stats(stats: &mut Vec<CylonStat>, folded: &mut usize)
+{
+    let mut output = vec![];
+
+    for mut stat in stats.drain(..)
+    {
+        match &mut stat
+        {
+            CylonStat::Goto { expression } |
+            CylonStat::Expression { expression } => fold_expr(expression, folded),
+            CylonStat::Assignment { value, .. } => fold_expr(value, folded),
+            CylonStat::If { condition, body, else_body } => {
+                fold_expr(condition, folded);
+                fold_stats(body, folded);
+                fold_stats(else_body, folded);
+
+                // An if on a constant number always takes the same branch, so only that branch needs to stay
+                if let CylonExpr::Number { num } = &*condition
+                {
+                    if let Ok(value) = num.parse::<f64>()
+                    {
+                        let branch = if value != 0.0 { body } else { else_body };
+                        output.append(branch);
+                        *folded += 1;
+                        continue
+                    }
+                }
+            },
+        }
+
+        output.push(stat);
+    }
+
+    *stats = output;
+}
+
+fn is_literal(expr: &CylonExpr) -> bool
+{
+    match expr
+    {
+        CylonExpr::Number { .. } |
+        CylonExpr::String { .. } => true,
+        // Negative numbers are written as a negation
+        CylonExpr::UnaryOp { operator, operand } => operator == "-" && matches!(**operand, CylonExpr::Number { .. }),
+        _ => false,
+    }
+}
+
+fn fold_expr(expr: &mut CylonExpr, folded: &mut usize)
+{
+    let foldable = match expr
+    {
+        CylonExpr::Group { group } => {
+            fold_expr(group, folded);
+            is_literal(group)
+        },
+        CylonExpr::BinaryOp { left, right, .. } => {
+            fold_expr(left, folded);
+            fold_expr(right, folded);
+            is_literal(left) && is_literal(right)
+        },
+        CylonExpr::UnaryOp { operator, operand } => {
+            fold_expr(operand, folded);
+            // A negated number is already as folded as it gets
+            let negative_number = operator == "-" && matches!(**operand, CylonExpr::Number { .. });
+            is_literal(operand) && !negative_number
+        },
+        _ => false,
+    };
+
+    if foldable
+    {
+        if let Some(value) = evaluate(expr)
+        {
+            *expr = value;
+            *folded += 1;
+        }
+    }
+}
+
+// Yolol numbers are fixed point with their own rounding rules, so rather than guess at them
+// the expression is handed to yoloxide. Anything that errors is left alone to error at runtime.
+fn evaluate(expr: &CylonExpr) -> Option<CylonExpr>
+{
+    let mut env = Environment::new("Optimizer");
+    yoloxide::execute_line(&mut env, format!("{}={}", SCRATCH_VARIABLE, minify::expression_string(expr)));
+
+    // An error skips the assignment, leaving nothing behind
+    let value = execution::contexts(&env).locals.into_iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(SCRATCH_VARIABLE))
+        .map(|(_, value)| value)?;
+
+    match value
+    {
+        LiteralValue::NumberVal(num) => {
+            let num = num.to_string();
+            match num.strip_prefix('-')
+            {
+                Some(magnitude) => Some(CylonExpr::UnaryOp {
+                    operator: "-".to_owned(),
+                    operand: Box::new(CylonExpr::Number { num: magnitude.to_owned() }),
+                }),
+                None => Some(CylonExpr::Number { num }),
+            }
+        },
+        // Yolol strings have no escapes, so a quote can't be written back out
+        LiteralValue::StringVal(string) if string.contains('"') => None,
+        LiteralValue::StringVal(string) => Some(CylonExpr::String { str: string }),
+    }
+}
+
+fn remove_dead_stores(prog: &mut CylonProg) -> Option<(String, Vec<String>)>
+{
+    let flow = ControlFlow::analyze(prog);
+
+    // Only locals, since anything can read a global field
+    let dead: Vec<String> = variables::analyze(prog, &flow).into_iter()
+        .filter(|usage| !usage.global && usage.written_never_read())
+        .map(|usage| usage.name)
+        .collect();
+
+    if dead.is_empty()
+    {
+        return None
+    }
+
+    let mut removed = 0;
+    for line in &mut prog.lines
+    {
+        remove_stores(&mut line.code, &dead, &mut removed);
+    }
+
+    if removed == 0 { None } else { Some((format!("removed {} store(s) to locals that are never read", removed), dead)) }
+}
+
+// Only stores whose value can't error are removed, since an error would skip the rest of the line
+fn remove_stores(stats: &mut Vec<CylonStat>, dead: &[String], removed: &mut usize)
+{
+    stats.retain(|stat| match stat
+    {
+        CylonStat::Assignment { identifier, operator, value } => {
+            let is_dead = operator == "=" && cannot_error(value) && dead.iter().any(|name| name.eq_ignore_ascii_case(identifier));
+            if is_dead
+            {
+                *removed += 1;
+            }
+            !is_dead
+        },
+        _ => true,
+    });
+
+    for stat in stats
+    {
+        if let CylonStat::If { body, else_body, .. } = stat
+        {
+            remove_stores(body, dead, removed);
+            remove_stores(else_body, dead, removed);
+        }
+    }
+}
+
+fn cannot_error(expr: &CylonExpr) -> bool
+{
+    match expr
+    {
+        CylonExpr::Group { group } => cannot_error(group),
+        CylonExpr::Identifier { .. } => true,
+        expr => is_literal(expr),
+    }
+}
+
+fn remove_after_goto(prog: &mut CylonProg) -> Option<(String, Vec<String>)>
+{
+    let mut removed = 0;
+
+    for line in &mut prog.lines
+    {
+        truncate_after_jump(&mut line.code, &mut removed);
+    }
+
+    if removed == 0 { None } else { Some((format!("removed {} statement(s) that come after a goto", removed), vec![])) }
+}
+
+fn truncate_after_jump(stats: &mut Vec<CylonStat>, removed: &mut usize)
+{
+    for stat in stats.iter_mut()
+    {
+        if let CylonStat::If { body, else_body, .. } = stat
+        {
+            truncate_after_jump(body, removed);
+            truncate_after_jump(else_body, removed);
+        }
+    }
+
+    let jump = stats.iter().position(|stat| control_flow::always_jumps(std::slice::from_ref(stat)));
+    if let Some(index) = jump
+    {
+        *removed += stats.len() - index - 1;
+        stats.truncate(index + 1);
+    }
+}
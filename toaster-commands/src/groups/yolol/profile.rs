@@ -0,0 +1,95 @@
+// Width of the heat bar drawn for the busiest line
+const BAR_WIDTH: usize = 20;
+// Longest bit of code shown next to each line before it gets cut off
+const PREVIEW_LENGTH: usize = 30;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct LineStats
+{
+    runs: usize,
+    // Runs where a runtime error skipped the rest of the line
+    errors: usize,
+}
+
+/// How often each line of a chip ran over an execution, and how often it errored out early
+pub struct Profile
+{
+    lines: Vec<LineStats>,
+}
+
+impl Profile
+{
+    pub fn new(line_count: usize) -> Self
+    {
+        Profile {
+            lines: vec![LineStats::default(); line_count],
+        }
+    }
+
+    /// Records one run of a 1-indexed line
+    pub fn record(&mut self, line: usize, errored: bool)
+    {
+        if let Some(stats) = self.lines.get_mut(line - 1)
+        {
+            stats.runs += 1;
+            if errored
+            {
+                stats.errors += 1;
+            }
+        }
+    }
+
+    /// Ranks the lines that ran by how much of the tick budget they took, busiest first.
+    /// Lines that never ran are listed at the end.
+    pub fn render(&self, code_lines: &[String]) -> String
+    {
+        let total_runs: usize = self.lines.iter().map(|stats| stats.runs).sum();
+        let busiest = self.lines.iter().map(|stats| stats.runs).max().unwrap_or(0);
+
+        let mut ranked: Vec<(usize, &LineStats)> = self.lines.iter()
+            .enumerate()
+            .map(|(index, stats)| (index + 1, stats))
+            .filter(|(_, stats)| stats.runs > 0)
+            .collect();
+        // Ties go to the earlier line so the order is stable
+        ranked.sort_by(|(line_a, stats_a), (line_b, stats_b)| stats_b.runs.cmp(&stats_a.runs).then(line_a.cmp(line_b)));
+
+        let mut output = vec![format!("line |  runs |  share | errors | {:<width$} | code", "heat", width = BAR_WIDTH)];
+
+        for (line, stats) in ranked
+        {
+            let share = stats.runs as f64 / total_runs as f64 * 100.0;
+            let filled = (stats.runs as f64 / busiest as f64 * BAR_WIDTH as f64).ceil() as usize;
+            let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+
+            output.push(format!("{:>4} | {:>5} | {:>5.1}% | {:>6} | {} | {}", line, stats.runs, share, stats.errors, bar, preview(code_lines, line)));
+        }
+
+        let never_ran: Vec<String> = self.lines.iter()
+            .enumerate()
+            .filter(|(_, stats)| stats.runs == 0)
+            .map(|(index, _)| (index + 1).to_string())
+            .collect();
+
+        if !never_ran.is_empty()
+        {
+            output.push(format!("\nNever ran: line(s) {}", never_ran.join(", ")));
+        }
+
+        output.join("\n")
+    }
+}
+
+fn preview(code_lines: &[String], line: usize) -> String
+{
+    let code = code_lines.get(line - 1).map(|code| code.trim()).unwrap_or("");
+
+    if code.chars().count() > PREVIEW_LENGTH
+    {
+        format!("{}...", code.chars().take(PREVIEW_LENGTH - 3).collect::<String>())
+    }
+    else
+    {
+        code.to_owned()
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+use serenity::prelude::*;
+use serenity::model::channel::Message;
+use serenity::http::AttachmentType;
+
+use toaster_core::edit_tracking;
+
+// Every yolol command answers with exactly one message. Sending it through here remembers which
+// message that was, so when the command gets edited and re-run the old answer is updated in place.
+
+/// Replies with some text, editing the previous reply to this message if there is one
+pub fn say(context: &Context, message: &Message, content: impl Display) -> serenity::Result<()>
+{
+    let content = content.to_string();
+
+    if let Some(mut previous) = previous_reply(context, message)
+    {
+        // Text edits can't take away a file, so those replies get replaced instead
+        if previous.attachments.is_empty()
+        {
+            return previous.edit(context, |m| m.content(&content))
+        }
+
+        previous.delete(context)?;
+    }
+
+    let reply = message.channel_id.say(context, content)?;
+    edit_tracking::track(message.id, reply.id);
+
+    Ok(())
+}
+
+/// Replies with a file. Attachments can't be edited, so any previous reply is replaced.
+pub fn send_file(context: &Context, message: &Message, bytes: &[u8], filename: &str, content: impl Display) -> serenity::Result<()>
+{
+    if let Some(previous) = previous_reply(context, message)
+    {
+        previous.delete(context)?;
+    }
+
+    let attachment = vec![AttachmentType::Bytes((bytes, filename))];
+    let reply = message.channel_id.send_files(context, attachment, |m| m.content(content))?;
+    edit_tracking::track(message.id, reply.id);
+
+    Ok(())
+}
+
+// A reply that was deleted in the meantime just means we start fresh
+fn previous_reply(context: &Context, message: &Message) -> Option<Message>
+{
+    let reply_id = edit_tracking::reply_for(message.id)?;
+    message.channel_id.message(context, reply_id).ok()
+}
@@ -0,0 +1,243 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use yoloxide::{
+    environment::Environment,
+    types::ast::value::LiteralValue,
+};
+
+use super::config::YololConfig;
+use super::execution;
+use super::snippets::{
+    DATA_DIRECTORY,
+    validate_name,
+};
+
+const ENVIRONMENT_DIRECTORY: &str = "yolol_environments";
+
+pub const MAX_ENVIRONMENTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind
+{
+    Number,
+    String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variable
+{
+    pub name: String,
+    pub kind: ValueKind,
+    pub value: String,
+}
+
+/// An environment flattened into plain data, so it can be stored and handed around as json.
+/// Globals keep their leading `:`, same as everywhere else.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedEnvironment
+{
+    pub name: String,
+    pub next_line: i64,
+    pub variables: Vec<Variable>,
+}
+
+impl SavedEnvironment
+{
+    pub fn capture(env: &Environment) -> Self
+    {
+        let contexts = execution::contexts(env);
+
+        let locals = contexts.locals.into_iter();
+
+        let globals = contexts.globals.into_iter()
+            .map(|(name, value)| (format!(":{}", name.trim_start_matches(':')), value));
+
+        let mut variables: Vec<Variable> = locals.chain(globals)
+            .map(|(name, value)| {
+                let (kind, value) = match value
+                {
+                    LiteralValue::NumberVal(num) => (ValueKind::Number, num.to_string()),
+                    LiteralValue::StringVal(string) => (ValueKind::String, string),
+                };
+
+                Variable {
+                    name,
+                    kind,
+                    value,
+                }
+            })
+            .collect();
+
+        // Hash map order changes between runs, which would make exports hard to diff
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        SavedEnvironment {
+            name: env.name.clone(),
+            next_line: env.next_line,
+            variables,
+        }
+    }
+
+    /// Makes sure every variable can be written back as a yolol assignment
+    pub fn validate(&self) -> Result<(), String>
+    {
+        for variable in &self.variables
+        {
+            YololConfig::parse_initial_value(&format!("{}={}", variable.name, self.literal(variable)))
+                .map_err(|_| format!("Can't restore `{}` with {} value `{}`!", variable.name, kind_name(variable.kind), variable.value))?;
+        }
+
+        Ok(())
+    }
+
+    fn literal(&self, variable: &Variable) -> String
+    {
+        match variable.kind
+        {
+            ValueKind::Number => variable.value.clone(),
+            ValueKind::String => format!("\"{}\"", variable.value),
+        }
+    }
+
+    /// Rebuilds the environment by assigning every variable, then putting the chip back on its line
+    pub fn restore(&self) -> Environment
+    {
+        let mut env = Environment::new(&self.name);
+
+        let assignments: Vec<(String, String)> = self.variables.iter()
+            .map(|variable| (variable.name.clone(), self.literal(variable)))
+            .collect();
+
+        execution::seed_environment(&mut env, &assignments);
+        env.next_line = self.next_line;
+
+        env
+    }
+}
+
+// A single chip's environment is written as one object, a network as a list with one per chip
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvironmentJson
+{
+    Single(SavedEnvironment),
+    Network(Vec<SavedEnvironment>),
+}
+
+pub fn to_json(envs: &[SavedEnvironment]) -> Result<String, String>
+{
+    let result = match envs
+    {
+        [env] => serde_json::to_string_pretty(env),
+        envs => serde_json::to_string_pretty(envs),
+    };
+
+    result.map_err(|error| format!("Failed to convert environment to json! Error: ```{}```", error))
+}
+
+/// Reads environments written by `to_json`, making sure there's one for each chip
+pub fn from_json(json: &str, chip_count: usize) -> Result<Vec<SavedEnvironment>, String>
+{
+    let envs = match serde_json::from_str(json)
+    {
+        Ok(EnvironmentJson::Single(env)) => vec![env],
+        Ok(EnvironmentJson::Network(envs)) => envs,
+        Err(error) => return Err(format!("Couldn't read the environment json! Error: ```{}```", error))
+    };
+
+    if envs.len() != chip_count
+    {
+        return Err(format!("Got {} environments for {} chips! There has to be one for each chip", envs.len(), chip_count))
+    }
+
+    for env in &envs
+    {
+        env.validate()?;
+    }
+
+    Ok(envs)
+}
+
+fn kind_name(kind: ValueKind) -> &'static str
+{
+    match kind
+    {
+        ValueKind::Number => "number",
+        ValueKind::String => "string",
+    }
+}
+
+// Environments are private to whoever made them. Names are case insensitive like everything else
+fn path(owner: u64, name: &str) -> PathBuf
+{
+    PathBuf::from(DATA_DIRECTORY)
+        .join(ENVIRONMENT_DIRECTORY)
+        .join(format!("user_{}", owner))
+        .join(format!("{}.json", name.to_lowercase()))
+}
+
+pub fn load(owner: u64, name: &str) -> Result<Option<SavedEnvironment>, String>
+{
+    validate_name(name)?;
+
+    let json = match fs::read_to_string(path(owner, name))
+    {
+        Ok(json) => json,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(format!("Failed to read environment `{}`! Error: ```{}```", name, error)),
+    };
+
+    let saved: SavedEnvironment = serde_json::from_str(&json)
+        .map_err(|error| format!("Environment `{}` is corrupted! Error: ```{}```", name, error))?;
+
+    saved.validate()?;
+    Ok(Some(saved))
+}
+
+pub fn save(owner: u64, name: &str, env: &SavedEnvironment) -> Result<(), String>
+{
+    validate_name(name)?;
+
+    let path = path(owner, name);
+
+    // Only new environments count against the limit
+    if !path.exists()
+    {
+        let existing = fs::read_dir(path.parent().expect("Environment path has no parent!"))
+            .map(|entries| entries.count())
+            .unwrap_or(0);
+
+        if existing >= MAX_ENVIRONMENTS
+        {
+            return Err(format!("You already have {} environments, which is the most there can be! Reset some first", MAX_ENVIRONMENTS))
+        }
+    }
+
+    let json = serde_json::to_string_pretty(env)
+        .map_err(|error| format!("Failed to convert environment to json! Error: ```{}```", error))?;
+
+    fs::create_dir_all(path.parent().expect("Environment path has no parent!"))
+        .and_then(|_| fs::write(&path, json))
+        .map_err(|error| format!("Failed to write environment to disk! Error: ```{}```", error))
+}
+
+/// Returns whether there was anything to reset
+pub fn reset(owner: u64, name: &str) -> Result<bool, String>
+{
+    validate_name(name)?;
+
+    match fs::remove_file(path(owner, name))
+    {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(format!("Failed to reset environment `{}`! Error: ```{}```", name, error)),
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use regex::Regex;
+use lazy_static::lazy_static;
+
+// Next to the rest of the bot's files, so anything saved here survives restarts
+pub const DATA_DIRECTORY: &str = "/home/toaster/fracking-toaster";
+const SNIPPET_DIRECTORY: &str = "yolol_snippets";
+
+// Keeps a single user or guild from filling up the disk
+pub const MAX_SNIPPETS: usize = 50;
+pub const MAX_SNIPPET_LENGTH: usize = 32 * 1024;
+
+lazy_static! {
+    // Names double as file names, so they're kept to characters that are safe in a path
+    static ref NAME_MATCHER: Regex = Regex::new(r"\A[a-zA-Z0-9_-]{1,32}\z").expect("Snippet name regex failed to compile!");
+}
+
+/// Who a snippet belongs to. User snippets are private to their author, guild snippets are shared with everyone in it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope
+{
+    User(u64),
+    Guild(u64),
+}
+
+impl Scope
+{
+    fn directory(self) -> PathBuf
+    {
+        let scope = match self
+        {
+            Scope::User(id) => format!("user_{}", id),
+            Scope::Guild(id) => format!("guild_{}", id),
+        };
+
+        PathBuf::from(DATA_DIRECTORY).join(SNIPPET_DIRECTORY).join(scope)
+    }
+}
+
+impl fmt::Display for Scope
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Scope::User(_) => write!(f, "personal"),
+            Scope::Guild(_) => write!(f, "server"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet
+{
+    pub name: String,
+    pub code: String,
+
+    pub author_id: u64,
+    pub author: String,
+    // RFC 3339 timestamp of the message that saved it
+    pub saved_at: String,
+}
+
+pub fn validate_name(name: &str) -> Result<(), String>
+{
+    if NAME_MATCHER.is_match(name)
+    {
+        Ok(())
+    }
+    else
+    {
+        Err(format!("`{}` can't be used as a name! Names are up to 32 letters, numbers, `-` or `_`", name))
+    }
+}
+
+// Yolol ignores case, so snippet names do too
+fn path(scope: Scope, name: &str) -> PathBuf
+{
+    scope.directory().join(format!("{}.json", name.to_lowercase()))
+}
+
+/// Saves a snippet, replacing any snippet with the same name in that scope.
+/// Returns whether an existing snippet was replaced.
+pub fn save(scope: Scope, snippet: &Snippet) -> Result<bool, String>
+{
+    validate_name(&snippet.name)?;
+
+    if snippet.code.len() > MAX_SNIPPET_LENGTH
+    {
+        return Err(format!("That snippet is too big! Snippets can be at most {} KiB", MAX_SNIPPET_LENGTH / 1024))
+    }
+
+    let replacing = load(scope, &snippet.name)?.is_some();
+    if !replacing && list(scope)?.len() >= MAX_SNIPPETS
+    {
+        return Err(format!("There's already {} {} snippets, which is the most there can be! Delete some first", MAX_SNIPPETS, scope))
+    }
+
+    let json = serde_json::to_string_pretty(snippet)
+        .map_err(|error| format!("Failed to convert snippet to json! Error: ```{}```", error))?;
+
+    fs::create_dir_all(scope.directory())
+        .and_then(|_| fs::write(path(scope, &snippet.name), json))
+        .map_err(|error| format!("Failed to write snippet to disk! Error: ```{}```", error))?;
+
+    Ok(replacing)
+}
+
+pub fn load(scope: Scope, name: &str) -> Result<Option<Snippet>, String>
+{
+    validate_name(name)?;
+
+    let json = match fs::read_to_string(path(scope, name))
+    {
+        Ok(json) => json,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(format!("Failed to read snippet `{}`! Error: ```{}```", name, error)),
+    };
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|error| format!("Snippet `{}` is corrupted! Error: ```{}```", name, error))
+}
+
+/// Looks in the user's own snippets first, then the guild's
+pub fn find(user_id: u64, guild_id: Option<u64>, name: &str) -> Result<Option<(Scope, Snippet)>, String>
+{
+    let scopes = std::iter::once(Scope::User(user_id)).chain(guild_id.map(Scope::Guild));
+
+    for scope in scopes
+    {
+        if let Some(snippet) = load(scope, name)?
+        {
+            return Ok(Some((scope, snippet)))
+        }
+    }
+
+    Ok(None)
+}
+
+/// Every snippet in a scope, sorted by name. Files that can't be read are skipped.
+pub fn list(scope: Scope) -> Result<Vec<Snippet>, String>
+{
+    let entries = match fs::read_dir(scope.directory())
+    {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(format!("Failed to list snippets! Error: ```{}```", error)),
+    };
+
+    let mut snippets: Vec<Snippet> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().map(|extension| extension == "json").unwrap_or(false))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+
+    snippets.sort_by_key(|snippet: &Snippet| snippet.name.to_lowercase());
+    Ok(snippets)
+}
+
+/// Returns whether there was anything to delete
+pub fn delete(scope: Scope, name: &str) -> Result<bool, String>
+{
+    validate_name(name)?;
+
+    match fs::remove_file(path(scope, name))
+    {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(format!("Failed to delete snippet `{}`! Error: ```{}```", name, error)),
+    }
+}
@@ -0,0 +1,290 @@
+use std::sync::{
+    mpsc,
+    Arc,
+    Mutex,
+};
+use std::time::Instant;
+
+use yoloxide::environment::Environment;
+
+use toaster_core::plugin_tasks;
+
+use super::config::YololConfig;
+use super::execution::{
+    self,
+    StopReason,
+    Snapshot,
+    TIME_LIMIT,
+    WORKER_GRACE,
+};
+
+// Most runs a single sweep is allowed to do, across every combination of values
+pub const MAX_COMBINATIONS: usize = 64;
+// How many runs happen at the same time
+const WORKERS: usize = 4;
+
+/// The outcome of running the program with one combination of swept values
+pub struct SweepRun
+{
+    pub assignments: Vec<(String, String)>,
+    pub result: Result<(StopReason, Snapshot), String>,
+}
+
+/// Every way of picking one value for each swept variable, in order with the last variable changing fastest
+pub fn combinations(sweeps: &[(String, Vec<String>)]) -> Result<Vec<Vec<(String, String)>>, String>
+{
+    let total = sweeps.iter()
+        .try_fold(1usize, |total, (_, values)| total.checked_mul(values.len()))
+        .filter(|&total| total <= MAX_COMBINATIONS);
+
+    if total.is_none()
+    {
+        return Err(format!("That sweep has too many combinations! A sweep can do at most {} runs", MAX_COMBINATIONS))
+    }
+
+    let mut combinations = vec![vec![]];
+
+    for (name, values) in sweeps
+    {
+        combinations = combinations.into_iter()
+            .flat_map(|combination: Vec<(String, String)>| values.iter().map(move |value| {
+                let mut combination = combination.clone();
+                combination.push((name.clone(), value.clone()));
+                combination
+            }))
+            .collect();
+    }
+
+    Ok(combinations)
+}
+
+/// Runs the chips once per combination of swept values, spread over a few worker threads.
+/// Results come back in the same order as the combinations.
+pub fn run_sweep(chips: Vec<Vec<String>>, config: &YololConfig, env_names: &[String]) -> Result<Vec<SweepRun>, String>
+{
+    let combinations = combinations(&config.sweeps)?;
+
+    // Catch a bad condition once here, rather than once in every row
+    if let Some(condition) = &config.stop_condition
+    {
+        execution::validate_condition(condition)?;
+    }
+
+    // Every run shares one time limit, so a big sweep takes no longer than a single run could
+    let deadline = Instant::now() + TIME_LIMIT;
+
+    let (sender, receiver) = mpsc::channel();
+
+    // Each worker keeps taking the next combination nobody has run yet until there are none left
+    let jobs = Arc::new(Mutex::new(combinations.clone().into_iter().enumerate()));
+    let chips = Arc::new(chips);
+    let config = Arc::new(config.clone());
+    let env_names = Arc::new(env_names.to_vec());
+
+    for _ in 0..WORKERS.min(combinations.len())
+    {
+        let jobs = jobs.clone();
+        let chips = chips.clone();
+        let config = config.clone();
+        let env_names = env_names.clone();
+        let sender = sender.clone();
+
+        let task = Box::new(move || loop {
+            // Taken on its own line, so the lock is let go of before the run starts
+            let job = jobs.lock().expect("Sweep jobs were poisoned!").next();
+            let (index, assignments) = match job
+            {
+                Some(job) => job,
+                None => break,
+            };
+
+            // Swept values go after the `--set` ones, so they win when both name the same variable
+            let mut run_config = (*config).clone();
+            run_config.initial_values.extend(assignments);
+
+            let mut envs: Vec<Environment> = env_names.iter().map(|name| Environment::new(name)).collect();
+
+            let result = if Instant::now() > deadline {
+                Err("ran out of time before it got a turn".to_owned())
+            }
+            else {
+                execution::run_network_until(&chips, &run_config, &mut envs, deadline, |_, _, _, _| ())
+                    .map(|stop_reason| (stop_reason, execution::snapshot(&envs[0])))
+            };
+
+            // If the sweep as a whole took too long, nobody is listening anymore
+            let _ = sender.send((index, result));
+        });
+
+        // Workers we give up on keep running, so they have to keep this library loaded until they're done
+        plugin_tasks::spawn(super::YOLOL_GROUP.name, "yolol-sweep".to_owned(), task)
+            .map_err(|error| format!("Couldn't start a worker to run your sweep on! Error: ```{}```", error))?;
+    }
+
+    // Runs stop themselves at the deadline, the grace only covers a single tick that takes forever
+    let deadline = deadline + WORKER_GRACE;
+
+    let mut results: Vec<Option<Result<(StopReason, Snapshot), String>>> = combinations.iter().map(|_| None).collect();
+
+    for _ in 0..combinations.len()
+    {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(timeout)
+        {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(_) => break,
+        }
+    }
+
+    Ok(combinations.into_iter()
+        .zip(results)
+        .map(|(assignments, result)| SweepRun {
+            assignments,
+            result: result.unwrap_or_else(|| Err("got stuck and was abandoned".to_owned())),
+        })
+        .collect())
+}
+
+fn stop_summary(stop_reason: &StopReason) -> String
+{
+    match stop_reason
+    {
+        StopReason::TickLimit(ticks) => format!("ran {} ticks", ticks),
+        StopReason::Condition(tick) => format!("until @ {}", tick),
+        StopReason::Settled(tick) => format!("settled @ {}", tick),
+        StopReason::TimedOut(tick) => format!("timed out @ {}", tick),
+        StopReason::MemoryLimit(tick) => format!("memory @ {}", tick),
+    }
+}
+
+/// Tabulates the runs, with a column for each swept variable, each chosen field and how the run ended.
+/// Without any chosen fields, every global that isn't being swept gets shown.
+pub fn render(runs: &[SweepRun], fields: &[String]) -> String
+{
+    let swept: Vec<String> = runs.first()
+        .map(|run| run.assignments.iter().map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default();
+
+    let fields: Vec<String> = if fields.is_empty() {
+        let mut globals: Vec<String> = runs.iter()
+            .filter_map(|run| run.result.as_ref().ok())
+            .flat_map(|(_, snapshot)| snapshot.keys())
+            .filter(|name| name.starts_with(':') && !swept.iter().any(|swept| swept.eq_ignore_ascii_case(name)))
+            .cloned()
+            .collect();
+
+        globals.sort();
+        globals.dedup();
+        globals
+    }
+    else {
+        fields.to_vec()
+    };
+
+    let mut header: Vec<String> = swept.iter().chain(fields.iter()).cloned().collect();
+    header.push("stopped".to_owned());
+
+    let mut rows = vec![header];
+
+    for run in runs
+    {
+        let mut row: Vec<String> = run.assignments.iter().map(|(_, value)| value.clone()).collect();
+
+        match &run.result
+        {
+            Ok((stop_reason, snapshot)) => {
+                row.extend(fields.iter().map(|field| {
+                    snapshot.iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(field))
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_else(|| "-".to_owned())
+                }));
+                row.push(stop_summary(stop_reason));
+            },
+            Err(error) => {
+                row.extend(fields.iter().map(|_| "-".to_owned()));
+                row.push(format!("error: {}", error));
+            },
+        }
+
+        rows.push(row);
+    }
+
+    // Pad every column but the last to its widest cell
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let last = row.len() - 1;
+            row.iter()
+                .enumerate()
+                .map(|(column, cell)| if column == last { cell.clone() } else { format!("{:<width$}", cell, width = widths[column]) })
+                .collect::<Vec<String>>()
+                .join(" | ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn sweep(name: &str, values: &[&str]) -> (String, Vec<String>)
+    {
+        (name.to_owned(), values.iter().map(|value| value.to_string()).collect())
+    }
+
+    fn assignments(pairs: &[(&str, &str)]) -> Vec<(String, String)>
+    {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn last_variable_changes_fastest()
+    {
+        let combinations = combinations(&[sweep(":a", &["1", "2"]), sweep(":b", &["x", "y"])]).unwrap();
+
+        assert_eq!(combinations, vec![
+            assignments(&[(":a", "1"), (":b", "x")]),
+            assignments(&[(":a", "1"), (":b", "y")]),
+            assignments(&[(":a", "2"), (":b", "x")]),
+            assignments(&[(":a", "2"), (":b", "y")]),
+        ]);
+    }
+
+    #[test]
+    fn no_sweeps_is_a_single_empty_run()
+    {
+        assert_eq!(combinations(&[]).unwrap(), vec![vec![]]);
+    }
+
+    #[test]
+    fn runs_come_back_in_combination_order()
+    {
+        let mut config = YololConfig::new();
+        config.tick_limit = 1;
+        config.sweeps = vec![sweep(":a", &["1", "2", "3", "4", "5", "6", "7"])];
+
+        let runs = run_sweep(vec![vec![":b = :a * 2".to_owned()]], &config, &["chip".to_owned()]).unwrap();
+        let doubled: Vec<Option<String>> = runs.iter()
+            .map(|run| run.result.as_ref().ok().and_then(|(_, snapshot)| snapshot.get(":b").cloned()))
+            .collect();
+
+        let expected: Vec<Option<String>> = (1..=7).map(|value| Some((value * 2).to_string())).collect();
+        assert_eq!(doubled, expected);
+    }
+
+    #[test]
+    fn too_many_combinations_are_refused()
+    {
+        let values: Vec<String> = (0..MAX_COMBINATIONS).map(|value| value.to_string()).collect();
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+
+        assert!(combinations(&[sweep(":a", &values)]).is_ok());
+        assert!(combinations(&[sweep(":a", &values), sweep(":b", &["1", "2"])]).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use cylon_ast::CylonExpr;
+
+// Words the tokenizer treats specially, which means they can't be used as variable names
+pub const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "end", "goto",
+    "and", "or", "not",
+    "abs", "sqrt", "sin", "cos", "tan", "asin", "acos", "atan", "arcsin", "arccos", "arctan",
+];
+
+/// Characters that can be part of a name or a number
+pub fn is_word_char(c: char) -> bool
+{
+    c.is_alphanumeric() || c == '_' || c == ':' || c == '.'
+}
+
+/// Looks through any parentheses to the expression inside them
+pub fn strip_groups(expr: &CylonExpr) -> &CylonExpr
+{
+    match expr
+    {
+        CylonExpr::Group { group } => strip_groups(group),
+        expr => expr,
+    }
+}
+
+/// How a unary operator is written next to its operand
+pub enum UnaryForm
+{
+    Prefix(&'static str),
+    Postfix(&'static str),
+    Keyword,
+}
+
+// Cylon spells increments and decrements out with a placeholder operand, since the
+// symbol alone doesn't say which side of the variable it goes on
+pub fn unary_form(operator: &str) -> UnaryForm
+{
+    match operator
+    {
+        "-" => UnaryForm::Prefix("-"),
+        "++a" => UnaryForm::Prefix("++"),
+        "--a" => UnaryForm::Prefix("--"),
+        "a++" => UnaryForm::Postfix("++"),
+        "a--" => UnaryForm::Postfix("--"),
+        "!" => UnaryForm::Postfix("!"),
+        _ => UnaryForm::Keyword,
+    }
+}
+
+pub fn is_increment(operator: &str) -> bool
+{
+    operator == "++a" || operator == "a++"
+}
+
+pub fn is_decrement(operator: &str) -> bool
+{
+    operator == "--a" || operator == "a--"
+}
@@ -0,0 +1,306 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    HashSet,
+};
+
+use cylon_ast::{
+    CylonProg,
+    CylonStat,
+    CylonExpr,
+};
+
+use super::control_flow::ControlFlow;
+use super::syntax::{
+    is_decrement,
+    is_increment,
+};
+
+#[derive(Debug, Default)]
+pub struct VariableUsage
+{
+    // The spelling used the first time the variable shows up, since yolol ignores case
+    pub name: String,
+    pub global: bool,
+
+    pub reads: BTreeSet<usize>,
+    pub writes: BTreeSet<usize>,
+
+    // Some path from line 1 reads it before anything has written to it
+    pub read_before_write: bool,
+}
+
+impl VariableUsage
+{
+    pub fn written_never_read(&self) -> bool
+    {
+        !self.writes.is_empty() && self.reads.is_empty()
+    }
+}
+
+// Variables that are definitely written by the time we get somewhere
+type Defined = HashSet<String>;
+
+enum Access<'a>
+{
+    Read(&'a str),
+    Write(&'a str),
+}
+
+/// Finds every variable in the program and where it gets read and written. Whether something is read
+/// before being written is worked out by following the control flow from line 1, only counting writes
+/// that happen on every path. Computed gotos are assumed to be able to land anywhere.
+pub fn analyze(prog: &CylonProg, flow: &ControlFlow) -> Vec<VariableUsage>
+{
+    let mut usages: BTreeMap<String, VariableUsage> = BTreeMap::new();
+
+    // Where every variable is touched, ignoring control flow entirely
+    for (index, line) in prog.lines.iter().enumerate()
+    {
+        let mut defined = Defined::new();
+        run_line(&line.code, &mut defined, &mut |access, _| {
+            let (name, is_write) = match access
+            {
+                Access::Read(name) => (name, false),
+                Access::Write(name) => (name, true),
+            };
+
+            let usage = usages.entry(name.to_lowercase()).or_insert_with(|| VariableUsage {
+                name: name.to_owned(),
+                global: name.starts_with(':'),
+                ..Default::default()
+            });
+
+            if is_write { usage.writes.insert(index + 1); } else { usage.reads.insert(index + 1); }
+        });
+    }
+
+    let in_states = definitely_defined(prog, flow);
+
+    // With the states settled, go through once more and catch any read of something not yet defined
+    for (index, line) in prog.lines.iter().enumerate()
+    {
+        if let Some(state) = &in_states[index]
+        {
+            let mut defined = state.clone();
+            run_line(&line.code, &mut defined, &mut |access, defined| {
+                if let Access::Read(name) = access
+                {
+                    let key = name.to_lowercase();
+                    if !defined.contains(&key)
+                    {
+                        if let Some(usage) = usages.get_mut(&key)
+                        {
+                            usage.read_before_write = true;
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    usages.into_values().collect()
+}
+
+// Classic must-be-defined dataflow. Each line starts with whatever is defined on every path into it,
+// `None` meaning no path has gotten there yet.
+fn definitely_defined(prog: &CylonProg, flow: &ControlFlow) -> Vec<Option<Defined>>
+{
+    let line_count = prog.lines.len();
+    let mut in_states: Vec<Option<Defined>> = vec![None; line_count];
+
+    if line_count == 0
+    {
+        return in_states
+    }
+
+    in_states[0] = Some(Defined::new());
+    let mut worklist = vec![1];
+
+    while let Some(line) = worklist.pop()
+    {
+        let mut defined = in_states[line - 1].clone().unwrap_or_default();
+        run_line(&prog.lines[line - 1].code, &mut defined, &mut |_, _| ());
+
+        let mut successors: Vec<usize> = flow.edges.iter()
+            .filter(|edge| edge.from == line)
+            .map(|edge| edge.to)
+            .collect();
+
+        if flow.computed_gotos.contains(&line)
+        {
+            successors.extend(1..=line_count);
+        }
+
+        for successor in successors
+        {
+            let state = &mut in_states[successor - 1];
+
+            let merged = match state
+            {
+                // Line 1 is also where execution starts, with nothing defined. That never changes
+                _ if successor == 1 => continue,
+                Some(existing) => existing.intersection(&defined).cloned().collect(),
+                None => defined.clone(),
+            };
+
+            if state.as_ref() != Some(&merged)
+            {
+                *state = Some(merged);
+                worklist.push(successor);
+            }
+        }
+    }
+
+    in_states
+}
+
+// Walks the statements of a line in execution order, reporting each access along with what's defined
+// at that point, and updating `defined` to what's definitely defined at the end of the line.
+// Returns whether the statements ended in a goto, since nothing after one runs.
+fn run_line<F>(stats: &[CylonStat], defined: &mut Defined, visit: &mut F) -> bool
+where
+    F: FnMut(Access, &Defined)
+{
+    for stat in stats
+    {
+        match stat
+        {
+            CylonStat::Goto { expression } => {
+                read_expr(expression, defined, visit);
+                return true
+            },
+
+            CylonStat::If { condition, body, else_body } => {
+                read_expr(condition, defined, visit);
+
+                let mut body_defined = defined.clone();
+                let body_jumps = run_line(body, &mut body_defined, visit);
+
+                let mut else_defined = defined.clone();
+                let else_jumps = run_line(else_body, &mut else_defined, visit);
+
+                // Only branches that fall out of the if carry on to the rest of the line
+                *defined = match (body_jumps, else_jumps)
+                {
+                    (true, true) => return true,
+                    (true, false) => else_defined,
+                    (false, true) => body_defined,
+                    (false, false) => body_defined.intersection(&else_defined).cloned().collect(),
+                };
+            },
+
+            CylonStat::Assignment { identifier, operator, value } => {
+                read_expr(value, defined, visit);
+
+                // Compound assignments like `a+=1` read the old value first
+                if operator != "="
+                {
+                    visit(Access::Read(identifier), defined);
+                }
+
+                visit(Access::Write(identifier), defined);
+                defined.insert(identifier.to_lowercase());
+            },
+
+            CylonStat::Expression { expression } => read_expr(expression, defined, visit),
+        }
+    }
+
+    false
+}
+
+fn read_expr<F>(expr: &CylonExpr, defined: &mut Defined, visit: &mut F)
+where
+    F: FnMut(Access, &Defined)
+{
+    match expr
+    {
+        CylonExpr::Group { group } => read_expr(group, defined, visit),
+        CylonExpr::BinaryOp { left, right, .. } => {
+            read_expr(left, defined, visit);
+            read_expr(right, defined, visit);
+        },
+        CylonExpr::UnaryOp { operator, operand } => {
+            read_expr(operand, defined, visit);
+
+            // Increments and decrements write back to their variable
+            if let CylonExpr::Identifier { name } = &**operand
+            {
+                if is_increment(operator) || is_decrement(operator)
+                {
+                    visit(Access::Write(name), defined);
+                    defined.insert(name.to_lowercase());
+                }
+            }
+        },
+        CylonExpr::Identifier { name } => visit(Access::Read(name), defined),
+        CylonExpr::Number { .. } |
+        CylonExpr::String { .. } => (),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::parse_yolol;
+    use super::super::config::YololInput;
+
+    // What's definitely defined coming into each line, sorted so it's easy to compare
+    fn defined_at(code: &str) -> Vec<Option<Vec<String>>>
+    {
+        let prog: CylonProg = parse_yolol(YololInput::Yolol(code.to_owned())).expect("Test code failed to parse!").into();
+        let flow = ControlFlow::analyze(&prog);
+
+        definitely_defined(&prog, &flow).into_iter()
+            .map(|state| state.map(|defined| {
+                let mut defined: Vec<String> = defined.into_iter().collect();
+                defined.sort();
+                defined
+            }))
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Option<Vec<String>>
+    {
+        Some(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[test]
+    fn writes_carry_on_to_the_next_line()
+    {
+        assert_eq!(defined_at("a=1\nb=a\nc=b"), vec![names(&[]), names(&["a"]), names(&["a", "b"])]);
+    }
+
+    #[test]
+    fn line_one_never_has_anything_defined()
+    {
+        assert_eq!(defined_at("a=1\ngoto 1")[0], names(&[]));
+    }
+
+    #[test]
+    fn one_branch_of_an_if_isnt_enough()
+    {
+        assert_eq!(defined_at("if :x then a=1 end b=1\nc=a")[1], names(&["b"]));
+        assert_eq!(defined_at("if :x then a=1 else a=2 end\nc=a")[1], names(&["a"]));
+    }
+
+    #[test]
+    fn paths_into_a_line_are_intersected()
+    {
+        assert_eq!(defined_at("b=1\na=1 goto 2")[1], names(&["b"]));
+    }
+
+    #[test]
+    fn lines_after_a_goto_are_unreachable()
+    {
+        assert_eq!(defined_at("a=1 goto 1\nb=2")[1], None);
+    }
+
+    #[test]
+    fn computed_gotos_can_land_anywhere()
+    {
+        assert_eq!(defined_at("a=1 goto :x\nb=2\nc=3")[1], names(&["a"]));
+    }
+}
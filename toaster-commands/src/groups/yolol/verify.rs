@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use super::execution::Snapshot;
+
+/// A variable that ended up different between the original and the round tripped program.
+/// `None` means the variable was never set on that side.
+pub struct Difference
+{
+    pub name: String,
+    pub original: Option<String>,
+    pub round_trip: Option<String>,
+}
+
+/// Compares two final states variable by variable, ignoring case like yolol does
+pub fn diff(original: &Snapshot, round_trip: &Snapshot) -> Vec<Difference>
+{
+    let lookup = |snapshot: &Snapshot, name: &str| snapshot.iter()
+        .find(|(key, _)| key.to_lowercase() == name)
+        .map(|(_, value)| value.clone());
+
+    let names: BTreeSet<String> = original.keys()
+        .chain(round_trip.keys())
+        .map(|name| name.to_lowercase())
+        .collect();
+
+    names.into_iter()
+        .filter_map(|name| {
+            let original = lookup(original, &name);
+            let round_trip = lookup(round_trip, &name);
+
+            if original == round_trip
+            {
+                return None
+            }
+
+            Some(Difference {
+                name,
+                original,
+                round_trip,
+            })
+        })
+        .collect()
+}
+
+/// Lines that aren't the same text after the round trip, as (1-indexed line, original, round tripped)
+pub fn changed_lines<'a>(original: &'a [String], round_trip: &'a [String]) -> Vec<(usize, &'a str, &'a str)>
+{
+    let line_count = original.len().max(round_trip.len());
+
+    (0..line_count)
+        .map(|index| {
+            let original = original.get(index).map(|line| line.trim()).unwrap_or("");
+            let round_trip = round_trip.get(index).map(|line| line.trim()).unwrap_or("");
+            (index + 1, original, round_trip)
+        })
+        .filter(|(_, original, round_trip)| original != round_trip)
+        .collect()
+}
@@ -24,6 +24,8 @@ use libloading::{
 use serenity::prelude::TypeMapKey;
 use serenity::framework::standard::CommandGroup;
 
+use crate::plugin_tasks;
+
 pub struct GroupLib
 {
     pub group: &'static CommandGroup,
@@ -234,9 +236,19 @@ impl PluginManager
         unloaded_group
     }
 
+    // Groups with threads still running their code are kept until a later flush, since unloading
+    // the library would pull the code out from under those threads
     pub fn flush_unload_buffer(&self)
     {
-        self.unload_buffer.lock().clear();
+        self.unload_buffer.lock().retain(|group_lib| {
+            let running = plugin_tasks::is_running(group_lib.group.name);
+            if running
+            {
+                println!("[PluginManager::flush_unload_buffer] Keeping group '{}' loaded, it still has threads running", group_lib.group.name);
+            }
+
+            running
+        });
     }
 
     // Not sure if this name is too obnoxiously long and I should just deal with a less useful name...
@@ -0,0 +1,59 @@
+// Keeps track of which replies belong to which command messages, so a command can be
+// re-run when its message is edited and update its old reply instead of posting a new one.
+//
+// This lives in core rather than with the commands since the handler needs to see it, and
+// statics in the command library don't survive a plugin reload anyway.
+
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+
+use parking_lot::Mutex;
+use lazy_static::lazy_static;
+
+use serenity::model::id::MessageId;
+
+// Old invocations are forgotten past this, so the map can't grow forever
+const MAX_TRACKED: usize = 500;
+
+#[derive(Default)]
+struct Tracker
+{
+    replies: HashMap<MessageId, MessageId>,
+    // Invocations in the order they were first tracked, oldest first
+    order: VecDeque<MessageId>,
+}
+
+lazy_static! {
+    static ref TRACKER: Mutex<Tracker> = Mutex::new(Tracker::default());
+}
+
+/// Records `reply` as the response to `invocation`, replacing any earlier reply
+pub fn track(invocation: MessageId, reply: MessageId)
+{
+    let mut tracker = TRACKER.lock();
+
+    if tracker.replies.insert(invocation, reply).is_none()
+    {
+        tracker.order.push_back(invocation);
+    }
+
+    while tracker.order.len() > MAX_TRACKED
+    {
+        if let Some(oldest) = tracker.order.pop_front()
+        {
+            tracker.replies.remove(&oldest);
+        }
+    }
+}
+
+pub fn reply_for(invocation: MessageId) -> Option<MessageId>
+{
+    TRACKER.lock().replies.get(&invocation).cloned()
+}
+
+pub fn is_tracked(invocation: MessageId) -> bool
+{
+    TRACKER.lock().replies.contains_key(&invocation)
+}
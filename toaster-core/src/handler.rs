@@ -1,7 +1,19 @@
 use serenity::prelude::*;
 
+use serenity::framework::Framework;
 use serenity::model::gateway::Ready;
 use serenity::model::id::ChannelId;
+use serenity::model::channel::{
+    Message,
+    Reaction,
+};
+use serenity::model::event::MessageUpdateEvent;
+
+use threadpool::ThreadPool;
+
+use crate::edit_tracking;
+use crate::reaction_forwarding;
+use crate::share_map_hack::ToasterHack;
 
 pub struct Handler;
 impl EventHandler for Handler
@@ -18,4 +30,50 @@ impl EventHandler for Handler
 
         channel_id.say(&ctx.http, "I'm back online!").expect("Unable to report online status to startup channel!");
     }
+
+    // Edits only re-run commands that asked for it by tracking their reply
+    fn message_update(&self, ctx: Context, _old_if_available: Option<Message>, new: Option<Message>, event: MessageUpdateEvent)
+    {
+        if !edit_tracking::is_tracked(event.id)
+        {
+            return
+        }
+
+        // Edits that don't touch the content, like embeds loading in, aren't worth a re-run
+        if event.content.is_none()
+        {
+            return
+        }
+
+        let message = match new
+        {
+            Some(message) => message,
+            None => match event.channel_id.message(&ctx.http, event.id)
+            {
+                Ok(message) => message,
+                Err(why) => {
+                    println!("[Handler::message_update] Unable to fetch edited message: {:?}", why);
+                    return
+                }
+            }
+        };
+
+        let mut framework = {
+            let data = ctx.data.read();
+            match data.get_toaster()
+            {
+                Some(framework) => framework,
+                None => return
+            }
+        };
+
+        // The client's own threadpool isn't reachable from here, so edits get a small one of their own
+        let threadpool = ThreadPool::with_name("edit_dispatch".to_owned(), 1);
+        framework.dispatch(ctx, message, &threadpool);
+    }
+
+    fn reaction_add(&self, _ctx: Context, reaction: Reaction)
+    {
+        reaction_forwarding::forward(reaction);
+    }
 }
\ No newline at end of file
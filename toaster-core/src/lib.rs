@@ -3,4 +3,7 @@
 pub mod toaster_framework;
 pub mod handler;
 pub mod dynamic_loading;
-pub mod share_map_hack;
\ No newline at end of file
+pub mod share_map_hack;
+pub mod edit_tracking;
+pub mod reaction_forwarding;
+pub mod plugin_tasks;
\ No newline at end of file
@@ -0,0 +1,57 @@
+// Threads that plugins start to keep working after a command returns, like a debugger waiting on
+// reaction buttons. They're counted per group so the group's library can be kept loaded until they
+// finish. The entry point of each thread lives here in core, so once the plugin's closure has
+// returned nothing on the thread points into the plugin anymore.
+
+use std::io;
+use std::thread;
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref RUNNING: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+}
+
+// Counts the task as finished when dropped, which also covers the task panicking
+struct Running(String);
+
+impl Drop for Running
+{
+    fn drop(&mut self)
+    {
+        let mut running = RUNNING.lock();
+
+        if let Some(count) = running.get_mut(&self.0)
+        {
+            *count -= 1;
+            if *count == 0
+            {
+                running.remove(&self.0);
+            }
+        }
+    }
+}
+
+/// Starts a named thread that keeps `group`'s library loaded for as long as it runs.
+/// The task is boxed so the thread itself gets built here, rather than in the plugin.
+pub fn spawn(group: &str, name: String, task: Box<dyn FnOnce() + Send>) -> io::Result<()>
+{
+    *RUNNING.lock().entry(group.to_owned()).or_insert(0) += 1;
+    let running = Running(group.to_owned());
+
+    thread::Builder::new()
+        .name(name)
+        .spawn(move || {
+            let _running = running;
+            task();
+        })
+        .map(|_| ())
+}
+
+/// Whether any thread started for the group is still running
+pub fn is_running(group: &str) -> bool
+{
+    RUNNING.lock().contains_key(group)
+}
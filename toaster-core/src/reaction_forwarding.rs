@@ -0,0 +1,52 @@
+// Passes reactions on a message along to whoever is waiting on them, like a command that's
+// driven by reaction buttons. Only plain channels cross over into the command library, so
+// nothing here points into code that could be unloaded out from under us.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{
+    self,
+    Sender,
+    Receiver,
+};
+
+use parking_lot::Mutex;
+use lazy_static::lazy_static;
+
+use serenity::model::id::MessageId;
+use serenity::model::channel::Reaction;
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<HashMap<MessageId, Sender<Reaction>>> = Mutex::new(HashMap::new());
+}
+
+/// Starts forwarding reactions added to `message`. Subscribing again replaces the old receiver.
+pub fn subscribe(message: MessageId) -> Receiver<Reaction>
+{
+    let (sender, receiver) = mpsc::channel();
+    SUBSCRIBERS.lock().insert(message, sender);
+    receiver
+}
+
+pub fn unsubscribe(message: MessageId)
+{
+    SUBSCRIBERS.lock().remove(&message);
+}
+
+/// Hands a reaction to its subscriber, if there is one. Subscribers that went away without
+/// unsubscribing are cleaned up here.
+pub fn forward(reaction: Reaction)
+{
+    let mut subscribers = SUBSCRIBERS.lock();
+    let message = reaction.message_id;
+
+    let delivered = match subscribers.get(&message)
+    {
+        Some(sender) => sender.send(reaction).is_ok(),
+        None => return
+    };
+
+    if !delivered
+    {
+        subscribers.remove(&message);
+    }
+}